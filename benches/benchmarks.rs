@@ -36,6 +36,51 @@ fn external(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn external_for_each(b: &mut Bencher) {
+    let left = build_left();
+    let right = build_right();
+
+    let left = &left;
+    let right = &right;
+
+    b.iter(|| {
+        left.difference(right).for_each(|x| {
+            test::black_box(x);
+        });
+    });
+}
+
+#[bench]
+fn external_rev(b: &mut Bencher) {
+    let left = build_left();
+    let right = build_right();
+
+    let left = &left;
+    let right = &right;
+
+    b.iter(|| {
+        for item in left.difference(right).rev() {
+            test::black_box(item);
+        }
+    });
+}
+
+#[bench]
+fn external_non_fused(b: &mut Bencher) {
+    let left = build_left();
+    let right = build_right();
+
+    let left = &left;
+    let right = &right;
+
+    b.iter(|| {
+        for item in NonFused(left.iter()).difference(NonFused(right.iter())) {
+            test::black_box(item);
+        }
+    });
+}
+
 #[bench]
 fn stdlib(b: &mut Bencher) {
     use std::collections::HashSet;
@@ -70,6 +115,175 @@ fn stdlib_no_overhead(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn union_external(b: &mut Bencher) {
+    let left = build_left();
+    let right = build_right();
+
+    let left = &left;
+    let right = &right;
+
+    b.iter(|| {
+        for item in left.sorted_union(right) {
+            test::black_box(item);
+        }
+    });
+}
+
+#[bench]
+fn union_stdlib(b: &mut Bencher) {
+    use std::collections::HashSet;
+
+    let left = build_left();
+    let right = build_right();
+
+    b.iter(|| {
+        let left: HashSet<_> = left.iter().collect();
+        let right: HashSet<_> = right.iter().collect();
+
+        for &item in left.union(&right) {
+            test::black_box(item);
+        }
+    });
+}
+
+#[bench]
+fn skewed_external(b: &mut Bencher) {
+    let small = build_small();
+    let large = build_large();
+
+    let small = &small;
+    let large = &large;
+
+    b.iter(|| {
+        for item in small.difference(large) {
+            test::black_box(item);
+        }
+    });
+}
+
+#[bench]
+fn skewed_internal(b: &mut Bencher) {
+    let small = build_small();
+    let large = build_large();
+
+    let small = &small;
+    let large = &large;
+
+    b.iter(|| {
+        small.iter_difference(large, |x| {
+            test::black_box(x);
+        });
+    });
+}
+
+#[bench]
+fn skewed_gallop(b: &mut Bencher) {
+    let small = build_small();
+    let large = build_large();
+
+    b.iter(|| {
+        for item in symdiff::gallop_difference(&small, &large) {
+            test::black_box(item);
+        }
+    });
+}
+
+#[bench]
+fn skewed_gallop_set(b: &mut Bencher) {
+    use std::collections::BTreeSet;
+
+    let small: BTreeSet<_> = build_small().into_iter().collect();
+    let large: BTreeSet<_> = build_large().into_iter().collect();
+
+    b.iter(|| {
+        for item in symdiff::gallop_set_difference(&small, &large) {
+            test::black_box(item);
+        }
+    });
+}
+
+#[bench]
+fn skewed_seek_left_only(b: &mut Bencher) {
+    let small = build_small();
+    let large = build_large();
+
+    b.iter(|| {
+        for item in symdiff::seek_difference(&small, &large).only(symdiff::Side::Left) {
+            test::black_box(item);
+        }
+    });
+}
+
+#[bench]
+fn primitive(b: &mut Bencher) {
+    let left: Vec<u32> = build_left().into_iter().map(|x| x as u32).collect();
+    let right: Vec<u32> = build_right().into_iter().map(|x| x as u32).collect();
+
+    let mut left_only = Vec::with_capacity(left.len());
+    let mut right_only = Vec::with_capacity(right.len());
+
+    b.iter(|| {
+        left_only.clear();
+        right_only.clear();
+        symdiff::primitive_difference_into(&left, &right, &mut left_only, &mut right_only);
+        test::black_box((&left_only, &right_only));
+    });
+}
+
+#[bench]
+fn primitive_generic(b: &mut Bencher) {
+    use symdiff::Tag;
+
+    let left: Vec<u32> = build_left().into_iter().map(|x| x as u32).collect();
+    let right: Vec<u32> = build_right().into_iter().map(|x| x as u32).collect();
+
+    let mut left_only = Vec::with_capacity(left.len());
+    let mut right_only = Vec::with_capacity(right.len());
+
+    b.iter(|| {
+        left_only.clear();
+        right_only.clear();
+        left.iter().iter_difference(&right, |tag| match tag {
+            Tag::Left(&x) => left_only.push(x),
+            Tag::Right(&x) => right_only.push(x),
+        });
+        test::black_box((&left_only, &right_only));
+    });
+}
+
+#[bench]
+fn partition_into(b: &mut Bencher) {
+    let left = build_left();
+    let right = build_right();
+
+    let mut left_only = Vec::new();
+    let mut right_only = Vec::new();
+
+    b.iter(|| {
+        left_only.clear();
+        right_only.clear();
+        left.iter()
+            .partition_difference_into(&right, &mut left_only, &mut right_only);
+        test::black_box((&left_only, &right_only));
+    });
+}
+
+/// Hides the `FusedIterator` impl of the iterator it wraps.
+struct NonFused<I>(I);
+
+impl<I: Iterator> Iterator for NonFused<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
 fn build_left() -> Vec<i32> {
     (0..1000).filter(|x| x % 13 != 0).collect()
 }
@@ -77,3 +291,11 @@ fn build_left() -> Vec<i32> {
 fn build_right() -> Vec<i32> {
     (1..1000).filter(|x| x % 23 != 0).collect()
 }
+
+fn build_small() -> Vec<i32> {
+    (0..10).map(|x| x * 10_007).collect()
+}
+
+fn build_large() -> Vec<i32> {
+    (0..100_000).filter(|x| x % 3 != 0).collect()
+}
@@ -0,0 +1,206 @@
+use core::error::Error;
+use core::fmt;
+use core::iter::Peekable;
+
+use {CheckedDiff, Diff, DiffError, Natural, Side, Tag};
+
+/// The first ordering violation found in either input.
+///
+/// `previous` is the item at `position - 1` and `next` the smaller item that
+/// followed it at `position`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnsortedError<T> {
+    pub side: Side,
+    pub position: usize,
+    pub previous: T,
+    pub next: T,
+}
+
+impl<T> fmt::Display for UnsortedError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} input is not sorted at position {}",
+            self.side, self.position
+        )
+    }
+}
+
+impl<T: fmt::Debug> Error for UnsortedError<T> {}
+
+/// Symmetric difference that verifies both inputs are sorted as it goes.
+///
+/// The first violation is yielded as an error, after which the iterator is
+/// exhausted.
+pub struct CheckedIter<Left, Right>
+where
+    Left: Iterator,
+    Left::Item: Ord,
+    Right: Iterator<Item = Left::Item>,
+{
+    inner: CheckedDiff<Left, Right, Natural>,
+}
+
+impl<Left, Right> CheckedIter<Left, Right>
+where
+    Left: Iterator,
+    Left::Item: Ord,
+    Right: Iterator<Item = Left::Item>,
+{
+    pub(crate) fn new(left: Left, right: Right) -> Self {
+        CheckedIter {
+            inner: Diff::new(left, right).validate().iter(),
+        }
+    }
+}
+
+impl<Left, Right> Iterator for CheckedIter<Left, Right>
+where
+    Left: Iterator,
+    Left::Item: Ord,
+    Right: Iterator<Item = Left::Item>,
+{
+    type Item = Result<Tag<Left::Item>, UnsortedError<Left::Item>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|item| item.map_err(unsorted))
+    }
+}
+
+/// The error of a diff that only validates.
+pub(crate) fn unsorted<T>(error: DiffError<T>) -> UnsortedError<T> {
+    match error {
+        DiffError::Unsorted(error) => error,
+        DiffError::Duplicate(_) => unreachable!("duplicates are not checked"),
+    }
+}
+
+/// Input adapter that panics on unsorted input when debug assertions are
+/// enabled. Otherwise it passes the input through untouched.
+pub struct AssertSorted<I: Iterator> {
+    #[cfg(debug_assertions)]
+    iter: Peekable<I>,
+    #[cfg(debug_assertions)]
+    side: Side,
+    #[cfg(debug_assertions)]
+    position: usize,
+    #[cfg(not(debug_assertions))]
+    iter: I,
+}
+
+impl<I> AssertSorted<I>
+where
+    I: Iterator,
+    I::Item: Ord,
+{
+    #[cfg(debug_assertions)]
+    pub(crate) fn new(iter: I, side: Side) -> Self {
+        AssertSorted {
+            iter: iter.peekable(),
+            side,
+            position: 0,
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    pub(crate) fn new(iter: I, _: Side) -> Self {
+        AssertSorted { iter }
+    }
+}
+
+impl<I> Iterator for AssertSorted<I>
+where
+    I: Iterator,
+    I::Item: Ord,
+{
+    type Item = I::Item;
+
+    #[cfg(debug_assertions)]
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let item = self.iter.next()?;
+        self.position += 1;
+
+        if let Some(next) = self.iter.peek() {
+            assert!(
+                item <= *next,
+                "{} input is not sorted at position {}",
+                self.side,
+                self.position
+            );
+        }
+
+        Some(item)
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn fold<B, F>(self, init: B, f: F) -> B
+    where
+        F: FnMut(B, I::Item) -> B,
+    {
+        self.iter.fold(init, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+    use SymmetricDifference;
+
+    #[test]
+    fn sorted_input_passes() {
+        let left: &[i32] = &[1, 2, 4];
+        let right: &[i32] = &[2, 3];
+
+        let checked: Result<Vec<_>, _> = left.difference_checked(right).collect();
+        let plain: Vec<_> = left.difference(right).collect();
+
+        assert_eq!(
+            checked
+                .unwrap()
+                .into_iter()
+                .map(Tag::unwrap)
+                .collect::<Vec<_>>(),
+            plain.into_iter().map(Tag::unwrap).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn first_violation_is_reported() {
+        let left: &[i32] = &[1, 2, 4];
+        let right: &[i32] = &[2, 3, 7, 5, 6];
+
+        let result: Result<Vec<_>, _> = left.difference_checked(right).collect();
+        let error = result.unwrap_err();
+
+        assert_eq!(error.side, Side::Right);
+        assert_eq!(error.position, 3);
+        assert_eq!((*error.previous, *error.next), (7, 5));
+
+        let mut seen = Vec::new();
+        let result = left.iter_difference_checked(right, |tag| seen.push(*tag.unwrap()));
+
+        assert_eq!(seen, vec![1, 3]);
+        assert_eq!(result.unwrap_err().position, 3);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "left input is not sorted at position 2")]
+    fn debug_checked_panics() {
+        let left: &[i32] = &[1, 3, 2];
+        let right: &[i32] = &[2];
+        left.difference_debug_checked(right).for_each(drop);
+    }
+}
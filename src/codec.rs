@@ -0,0 +1,526 @@
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use std::mem;
+use std::string::String;
+use std::vec::Vec;
+
+use Tag;
+
+const MAGIC: &[u8; 4] = b"SYMD";
+const VERSION: u8 = 1;
+
+/// Items that can be stored in the binary delta format as their bytes.
+///
+/// Each element is stored behind a length prefix. To store a type of your
+/// own, implement `write_bytes` and `from_bytes` and pick a `KIND` of 32 or
+/// more; smaller kinds are reserved for the types this crate covers.
+pub trait Element: Ord + Sized {
+    /// Names the element type in the header, so that a stream is only decoded
+    /// as the type it was encoded from.
+    const KIND: u8;
+
+    /// Appends the bytes of `self` to `out`.
+    fn write_bytes(&self, out: &mut Vec<u8>);
+
+    /// Returns `None` if `bytes` were not written by `write_bytes`.
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+/// Items the delta format can store: primitive integers, as varint gaps from
+/// the item before them, which sorted input keeps small, and any `Element`.
+pub trait Encode: Ord + Sized + private::Sealed {
+    #[doc(hidden)]
+    const KIND: u8;
+
+    // Integers map onto `u64` in an order-preserving way, so that sorted
+    // values always have non-negative gaps. Other items are only stored as
+    // bytes.
+    #[doc(hidden)]
+    const GAPS: bool;
+
+    #[doc(hidden)]
+    fn to_bits(&self) -> u64;
+
+    #[doc(hidden)]
+    fn from_bits(bits: u64) -> Option<Self>;
+
+    #[doc(hidden)]
+    fn write_bytes(&self, out: &mut Vec<u8>);
+
+    #[doc(hidden)]
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl<T: super::Element> Sealed for T {}
+}
+
+impl<T: Element> Encode for T {
+    const KIND: u8 = <T as Element>::KIND;
+    const GAPS: bool = false;
+
+    fn to_bits(&self) -> u64 {
+        unreachable!("elements are stored as bytes")
+    }
+
+    fn from_bits(_: u64) -> Option<Self> {
+        unreachable!("elements are stored as bytes")
+    }
+
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        Element::write_bytes(self, out)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Element::from_bytes(bytes)
+    }
+}
+
+macro_rules! unsigned {
+    ($($ty:ty => $kind:expr),*) => {$(
+        impl private::Sealed for $ty {}
+
+        impl Encode for $ty {
+            const KIND: u8 = $kind;
+            const GAPS: bool = true;
+
+            fn to_bits(&self) -> u64 {
+                *self as u64
+            }
+
+            fn from_bits(bits: u64) -> Option<Self> {
+                <$ty>::try_from(bits).ok()
+            }
+
+            fn write_bytes(&self, _: &mut Vec<u8>) {
+                unreachable!("integers are stored as gaps")
+            }
+
+            fn from_bytes(_: &[u8]) -> Option<Self> {
+                unreachable!("integers are stored as gaps")
+            }
+        }
+    )*};
+}
+
+macro_rules! signed {
+    ($($ty:ty => $kind:expr),*) => {$(
+        impl private::Sealed for $ty {}
+
+        impl Encode for $ty {
+            const KIND: u8 = $kind;
+            const GAPS: bool = true;
+
+            fn to_bits(&self) -> u64 {
+                (*self as i64 as u64) ^ (1 << 63)
+            }
+
+            fn from_bits(bits: u64) -> Option<Self> {
+                <$ty>::try_from((bits ^ (1 << 63)) as i64).ok()
+            }
+
+            fn write_bytes(&self, _: &mut Vec<u8>) {
+                unreachable!("integers are stored as gaps")
+            }
+
+            fn from_bytes(_: &[u8]) -> Option<Self> {
+                unreachable!("integers are stored as gaps")
+            }
+        }
+    )*};
+}
+
+unsigned!(u8 => 1, u16 => 2, u32 => 3, u64 => 4);
+signed!(i8 => 5, i16 => 6, i32 => 7, i64 => 8);
+
+impl Element for Vec<u8> {
+    const KIND: u8 = 9;
+
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self);
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+impl Element for String {
+    const KIND: u8 = 10;
+
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+/// 32-bit FNV-1a over everything but the checksum itself.
+struct Checksum(u32);
+
+impl Checksum {
+    fn new() -> Self {
+        Checksum(0x811c_9dc5)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u32::from(byte)).wrapping_mul(0x0100_0193);
+        }
+    }
+}
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Streams a sorted `Tag` stream into a `Write`.
+///
+/// The output starts with a header naming the element type. Each item starts
+/// with a varint holding its side and either its gap from the previous item,
+/// for integers, or the length of the bytes that follow. `finish` writes an
+/// end marker, the item count and a checksum.
+pub struct Encoder<W: Write, T> {
+    writer: W,
+    previous: Option<T>,
+    bits: u64,
+    count: u64,
+    checksum: Checksum,
+    bytes: Vec<u8>,
+}
+
+impl<W: Write, T: Encode> Encoder<W, T> {
+    pub fn new(writer: W) -> io::Result<Self> {
+        let mut encoder = Encoder {
+            writer,
+            previous: None,
+            bits: 0,
+            count: 0,
+            checksum: Checksum::new(),
+            bytes: Vec::new(),
+        };
+        encoder.put(MAGIC)?;
+        encoder.put(&[VERSION, T::KIND])?;
+        Ok(encoder)
+    }
+
+    /// Fails with `InvalidInput` if `tag` is smaller than the item before it.
+    pub fn push(&mut self, tag: Tag<T>) -> io::Result<()> {
+        let side = tag.is_right() as u128;
+        let value = tag.unwrap();
+        if self
+            .previous
+            .as_ref()
+            .is_some_and(|previous| value < *previous)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "delta is not sorted",
+            ));
+        }
+
+        // Zero is reserved for the end marker.
+        if T::GAPS {
+            let bits = value.to_bits();
+            self.put_varint(((u128::from(bits - self.bits) << 1) | side) + 1)?;
+            self.bits = bits;
+        } else {
+            let mut bytes = mem::take(&mut self.bytes);
+            bytes.clear();
+            value.write_bytes(&mut bytes);
+            self.put_varint((((bytes.len() as u128) << 1) | side) + 1)?;
+            self.put(&bytes)?;
+            self.bytes = bytes;
+        }
+
+        self.previous = Some(value);
+        self.count += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.put_varint(0)?;
+        self.put_varint(u128::from(self.count))?;
+        let checksum = self.checksum.0;
+        self.writer.write_all(&checksum.to_le_bytes())?;
+        Ok(self.writer)
+    }
+
+    fn put(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.checksum.update(bytes);
+        self.writer.write_all(bytes)
+    }
+
+    fn put_varint(&mut self, mut value: u128) -> io::Result<()> {
+        let mut buf = [0; 19];
+        let mut len = 0;
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                buf[len] = byte;
+                len += 1;
+                break;
+            }
+            buf[len] = byte | 0x80;
+            len += 1;
+        }
+        self.put(&buf[..len])
+    }
+}
+
+/// Encodes a whole `Tag` stream, returning the writer once it is finished.
+pub fn encode<I, T, W>(delta: I, writer: W) -> io::Result<W>
+where
+    I: IntoIterator<Item = Tag<T>>,
+    T: Encode,
+    W: Write,
+{
+    let mut encoder = Encoder::new(writer)?;
+    for tag in delta {
+        encoder.push(tag)?;
+    }
+    encoder.finish()
+}
+
+/// Streams a `Tag` stream back out of a `Read`.
+///
+/// The header is checked by `new`; the count and checksum are checked once
+/// the end marker is reached, so a damaged stream yields `InvalidData` as its
+/// last item. Reads go a byte at a time, so wrap unbuffered readers in a
+/// `BufReader`.
+pub struct Decoder<R: Read, T> {
+    reader: R,
+    previous: u64,
+    count: u64,
+    checksum: Checksum,
+    bytes: Vec<u8>,
+    done: bool,
+    marker: PhantomData<T>,
+}
+
+impl<R: Read, T: Encode> Decoder<R, T> {
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut decoder = Decoder {
+            reader,
+            previous: 0,
+            count: 0,
+            checksum: Checksum::new(),
+            bytes: Vec::new(),
+            done: false,
+            marker: PhantomData,
+        };
+
+        let mut header = [0; 6];
+        decoder.read_bytes(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("not a symdiff delta"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data("unsupported delta version"));
+        }
+        if header[5] != T::KIND {
+            return Err(invalid_data("delta holds a different element type"));
+        }
+        Ok(decoder)
+    }
+
+    fn read_bytes(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.reader.read_exact(buf)?;
+        self.checksum.update(buf);
+        Ok(())
+    }
+
+    fn read_varint(&mut self) -> io::Result<u128> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let mut byte = [0];
+            self.read_bytes(&mut byte)?;
+            if shift >= 128 {
+                return Err(invalid_data("varint is too long"));
+            }
+            value |= u128::from(byte[0] & 0x7f) << shift;
+            if byte[0] & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn read_tag(&mut self) -> io::Result<Option<Tag<T>>> {
+        let word = self.read_varint()?;
+        if word == 0 {
+            self.read_trailer()?;
+            return Ok(None);
+        }
+
+        let word = word - 1;
+        let value = if T::GAPS {
+            let bits = u128::from(self.previous) + (word >> 1);
+            if bits > u128::from(u64::MAX) {
+                return Err(invalid_data("gap is out of range"));
+            }
+            self.previous = bits as u64;
+            T::from_bits(self.previous).ok_or_else(|| invalid_data("value is out of range"))?
+        } else {
+            self.read_element(word >> 1)?
+        };
+
+        self.count += 1;
+        Ok(Some(if word & 1 == 0 {
+            Tag::Left(value)
+        } else {
+            Tag::Right(value)
+        }))
+    }
+
+    fn read_element(&mut self, len: u128) -> io::Result<T> {
+        let len = u64::try_from(len).map_err(|_| invalid_data("element is too long"))?;
+
+        // Read through `take` so that a damaged length cannot allocate more
+        // than the stream holds.
+        let mut bytes = mem::take(&mut self.bytes);
+        bytes.clear();
+        (&mut self.reader).take(len).read_to_end(&mut bytes)?;
+        if (bytes.len() as u64) < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.checksum.update(&bytes);
+
+        let value = T::from_bytes(&bytes);
+        self.bytes = bytes;
+        value.ok_or_else(|| invalid_data("element is malformed"))
+    }
+
+    fn read_trailer(&mut self) -> io::Result<()> {
+        if self.read_varint()? != u128::from(self.count) {
+            return Err(invalid_data("delta length does not match"));
+        }
+
+        let expected = self.checksum.0;
+        let mut checksum = [0; 4];
+        self.reader.read_exact(&mut checksum)?;
+        if u32::from_le_bytes(checksum) != expected {
+            return Err(invalid_data("delta checksum does not match"));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read, T: Encode> Iterator for Decoder<R, T> {
+    type Item = io::Result<Tag<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.read_tag() {
+            Ok(Some(tag)) => Some(Ok(tag)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+    use SymmetricDifference;
+
+    fn round_trip<T: Encode>(delta: Vec<Tag<T>>) -> Vec<Tag<T>> {
+        let bytes = encode(delta, Vec::new()).unwrap();
+        Decoder::new(&bytes[..])
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn dense_deltas_take_a_byte_per_item() {
+        let left: Vec<u32> = (0..1000).filter(|x| x % 3 != 0).collect();
+        let right: Vec<u32> = (0..1000).filter(|x| x % 5 != 0).collect();
+        let delta: Vec<_> = left
+            .iter()
+            .cloned()
+            .difference(right.iter().cloned())
+            .collect();
+
+        let bytes = encode(delta.iter().cloned(), Vec::new());
+        assert!(bytes.unwrap().len() < delta.len() + 16);
+        assert_eq!(round_trip(delta.clone()), delta);
+    }
+
+    #[test]
+    fn signed_extremes_and_repeats_survive() {
+        let delta = vec![
+            Tag::Left(i64::MIN),
+            Tag::Right(i64::MIN),
+            Tag::Left(-1),
+            Tag::Left(-1),
+            Tag::Right(0),
+            Tag::Right(i64::MAX),
+        ];
+        assert_eq!(round_trip(delta.clone()), delta);
+
+        let unsorted = encode(vec![Tag::Left(2u8), Tag::Right(1)], Vec::new());
+        assert_eq!(unsorted.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn damage_is_detected() {
+        let bytes = encode((0..100u16).map(Tag::Right), Vec::new()).unwrap();
+
+        let mut flipped = bytes.clone();
+        flipped[20] ^= 0x02;
+        let result: io::Result<Vec<Tag<u16>>> = Decoder::new(&flipped[..]).unwrap().collect();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        let result: io::Result<Vec<Tag<u16>>> =
+            Decoder::new(&bytes[..bytes.len() - 1]).unwrap().collect();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        assert!(Decoder::<_, u32>::new(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn strings_and_byte_records_round_trip() {
+        let left = ["apple", "fig", "kiwi", "pear"];
+        let right = ["fig", "grape", "pear", "plum"];
+        let delta: Vec<_> = left
+            .iter()
+            .map(|s| s.to_string())
+            .difference(right.iter().map(|s| s.to_string()))
+            .collect();
+        assert_eq!(round_trip(delta.clone()), delta);
+
+        let records = vec![
+            Tag::Left(vec![]),
+            Tag::Right(vec![0, 0xff]),
+            Tag::Left(vec![1]),
+        ];
+        let bytes = encode(records.clone(), Vec::new()).unwrap();
+        assert!(Decoder::<_, String>::new(&bytes[..]).is_err());
+        assert_eq!(round_trip(records.clone()), records);
+
+        let unsorted = encode(vec![Tag::Left(vec![2u8]), Tag::Right(vec![1])], Vec::new());
+        assert_eq!(unsorted.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        let result: io::Result<Vec<Tag<Vec<u8>>>> =
+            Decoder::new(&bytes[..bytes.len() - 8]).unwrap().collect();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,169 @@
+use core::cmp::Ordering;
+
+use Tag;
+
+/// Ordering strategy used to merge two sorted inputs.
+///
+/// Items are converted into entries as they are pulled from either side so
+/// that a comparator may cache whatever it needs (a key, for instance) for
+/// the lifetime of the item inside the merge.
+pub trait Comparator<T> {
+    type Entry;
+
+    fn entry(&mut self, item: T) -> Self::Entry;
+    fn compare(&mut self, a: &Self::Entry, b: &Self::Entry) -> Ordering;
+    fn into_item(entry: Self::Entry) -> T;
+}
+
+/// Compares items by their `Ord` implementation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Natural;
+
+impl<T: Ord> Comparator<T> for Natural {
+    type Entry = T;
+
+    #[inline]
+    fn entry(&mut self, item: T) -> T {
+        item
+    }
+
+    #[inline]
+    fn compare(&mut self, a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+
+    #[inline]
+    fn into_item(entry: T) -> T {
+        entry
+    }
+}
+
+/// Compares items by their `Ord` implementation, looking through any `Tag`s
+/// around them, so that the output of one difference can be merged into
+/// another by value.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ByValue;
+
+/// Items `ByValue` can compare: anything `Ord`, and tags around such items.
+pub trait Valued: private::Sealed {
+    #[doc(hidden)]
+    fn cmp_value(&self, other: &Self) -> Ordering;
+}
+
+mod private {
+    use Tag;
+
+    pub trait Sealed {}
+
+    impl<T: Ord> Sealed for T {}
+    impl<T: Sealed> Sealed for Tag<T> {}
+}
+
+impl<T: Ord> Valued for T {
+    #[inline]
+    fn cmp_value(&self, other: &T) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+impl<T: Valued> Valued for Tag<T> {
+    #[inline]
+    fn cmp_value(&self, other: &Tag<T>) -> Ordering {
+        self.value().cmp_value(other.value())
+    }
+}
+
+impl<T: Valued> Comparator<T> for ByValue {
+    type Entry = T;
+
+    #[inline]
+    fn entry(&mut self, item: T) -> T {
+        item
+    }
+
+    #[inline]
+    fn compare(&mut self, a: &T, b: &T) -> Ordering {
+        a.cmp_value(b)
+    }
+
+    #[inline]
+    fn into_item(entry: T) -> T {
+        entry
+    }
+}
+
+impl<T, F> Comparator<T> for F
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    type Entry = T;
+
+    #[inline]
+    fn entry(&mut self, item: T) -> T {
+        item
+    }
+
+    #[inline]
+    fn compare(&mut self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+
+    #[inline]
+    fn into_item(entry: T) -> T {
+        entry
+    }
+}
+
+/// Compares items by a key, extracting the key anew for every comparison.
+#[derive(Clone, Copy, Debug)]
+pub struct ByKey<F>(pub F);
+
+impl<T, F, K> Comparator<T> for ByKey<F>
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    type Entry = T;
+
+    #[inline]
+    fn entry(&mut self, item: T) -> T {
+        item
+    }
+
+    #[inline]
+    fn compare(&mut self, a: &T, b: &T) -> Ordering {
+        (self.0)(a).cmp(&(self.0)(b))
+    }
+
+    #[inline]
+    fn into_item(entry: T) -> T {
+        entry
+    }
+}
+
+/// Compares items by a key, extracting the key once per item.
+#[derive(Clone, Copy, Debug)]
+pub struct ByCachedKey<F>(pub F);
+
+impl<T, F, K> Comparator<T> for ByCachedKey<F>
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    type Entry = (K, T);
+
+    #[inline]
+    fn entry(&mut self, item: T) -> (K, T) {
+        ((self.0)(&item), item)
+    }
+
+    #[inline]
+    fn compare(&mut self, a: &(K, T), b: &(K, T)) -> Ordering {
+        a.0.cmp(&b.0)
+    }
+
+    #[inline]
+    fn into_item(entry: (K, T)) -> T {
+        entry.1
+    }
+}
@@ -0,0 +1,193 @@
+use core::cmp::Ordering;
+use core::iter::Peekable;
+
+use merge::{MergeIter, Merged};
+use Tag;
+
+/// Rebuilds the right-hand input of a diff from its left-hand input and the
+/// `Tag` stream produced by `difference`.
+///
+/// `Tag::Left` items are removed from the base, one copy each, and
+/// `Tag::Right` items are inserted in order. Removals of items the base does
+/// not hold are ignored.
+pub struct Apply<Base, Delta>
+where
+    Base: Iterator,
+    Delta: Iterator<Item = Tag<Base::Item>>,
+{
+    base: Peekable<Base>,
+    delta: Peekable<Delta>,
+}
+
+impl<Base, Delta> Iterator for Apply<Base, Delta>
+where
+    Base: Iterator,
+    Base::Item: Ord,
+    Delta: Iterator<Item = Tag<Base::Item>>,
+{
+    type Item = Base::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let order = match (self.base.peek(), self.delta.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => return self.base.next(),
+                (None, Some(_)) => Ordering::Greater,
+                (Some(item), Some(tag)) => item.cmp(tag.value()),
+            };
+
+            match self.delta.peek() {
+                Some(&Tag::Right(_)) if order != Ordering::Less => {
+                    return self.delta.next().map(Tag::unwrap);
+                }
+
+                Some(&Tag::Left(_)) if order != Ordering::Less => {
+                    self.delta.next();
+                    if order == Ordering::Equal {
+                        self.base.next();
+                    }
+                }
+
+                _ => return self.base.next(),
+            }
+        }
+    }
+}
+
+pub fn apply<Base, Delta>(base: Base, delta: Delta) -> Apply<Base::IntoIter, Delta::IntoIter>
+where
+    Base: IntoIterator,
+    Base::Item: Ord,
+    Delta: IntoIterator<Item = Tag<Base::Item>>,
+{
+    Apply {
+        base: base.into_iter().peekable(),
+        delta: delta.into_iter().peekable(),
+    }
+}
+
+/// The delta from the right-hand input back to the left-hand one.
+pub struct Invert<Delta>(Delta);
+
+impl<Delta, T> Iterator for Invert<Delta>
+where
+    Delta: Iterator<Item = Tag<T>>,
+{
+    type Item = Tag<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Tag::invert)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+pub fn invert<Delta, T>(delta: Delta) -> Invert<Delta::IntoIter>
+where
+    Delta: IntoIterator<Item = Tag<T>>,
+{
+    Invert(delta.into_iter())
+}
+
+type ByValue<T> = fn(&T, &T) -> Ordering;
+
+/// Merges the deltas A→B and B→C into the delta A→C.
+///
+/// An item added by one delta and removed by the other cancels out; anything
+/// else passes through unchanged.
+pub struct Compose<First, Second>
+where
+    First: Iterator,
+    Second: Iterator,
+{
+    inner: MergeIter<First, Second, ByValue<First::Item>>,
+    rem: Option<First::Item>,
+}
+
+impl<First, Second, T> Iterator for Compose<First, Second>
+where
+    First: Iterator<Item = Tag<T>>,
+    Second: Iterator<Item = Tag<T>>,
+    T: Ord,
+{
+    type Item = Tag<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(rem) = self.rem.take() {
+            return Some(rem);
+        }
+
+        loop {
+            match self.inner.next()? {
+                Merged::Left(tag) | Merged::Right(tag) => return Some(tag),
+                Merged::Both(Tag::Left(_), Tag::Right(_))
+                | Merged::Both(Tag::Right(_), Tag::Left(_)) => (),
+                Merged::Both(first, second) => {
+                    self.rem = Some(second);
+                    return Some(first);
+                }
+            }
+        }
+    }
+}
+
+fn by_value<T: Ord>(a: &Tag<T>, b: &Tag<T>) -> Ordering {
+    a.value().cmp(b.value())
+}
+
+pub fn compose<First, Second, T>(
+    first: First,
+    second: Second,
+) -> Compose<First::IntoIter, Second::IntoIter>
+where
+    First: IntoIterator<Item = Tag<T>>,
+    Second: IntoIterator<Item = Tag<T>>,
+    T: Ord,
+{
+    Compose {
+        inner: MergeIter::new(first.into_iter(), second.into_iter(), by_value),
+        rem: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixtures::{LEFT as A, RIGHT as B};
+    use std::prelude::v1::*;
+    use SymmetricDifference;
+
+    static C: &[i32] = &[0, 1, 3, 5, 7, 9, 16];
+
+    #[test]
+    fn apply_and_invert_round_trip() {
+        let forward: Vec<_> = A.difference(B).collect();
+        let b: Vec<_> = apply(A, forward).cloned().collect();
+        assert_eq!(b, B);
+
+        let a: Vec<_> = apply(B, invert(A.difference(B))).cloned().collect();
+        assert_eq!(a, A);
+    }
+
+    #[test]
+    fn apply_handles_repeated_items() {
+        let a = vec![1, 1, 1, 2];
+        let b = vec![1, 2, 2, 3];
+        let rebuilt: Vec<_> = apply(a.clone(), a.difference(b.clone())).collect();
+        assert_eq!(rebuilt, b);
+    }
+
+    #[test]
+    fn compose_skips_the_middle() {
+        let composed: Vec<_> = compose(A.difference(B), B.difference(C)).collect();
+        let direct: Vec<_> = A.difference(C).collect();
+        assert_eq!(composed, direct);
+
+        let c: Vec<_> = apply(A, compose(A.difference(B), B.difference(C)))
+            .cloned()
+            .collect();
+        assert_eq!(c, C);
+    }
+}
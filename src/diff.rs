@@ -0,0 +1,600 @@
+use core::cmp::Ordering::*;
+use core::error::Error;
+use core::fmt;
+use core::marker::PhantomData;
+
+use compare::{ByCachedKey, ByKey, Comparator, Natural};
+use merge::{self, MergeIter, Merged};
+use visit::DiffVisitor;
+use {DuplicateError, Duplicates, Side, SymDiffIter, Tag, UnsortedError};
+
+/// `Diff` state: no checks are made, so items come out bare.
+pub struct Unchecked;
+
+/// `Diff` state: a duplicate policy or validation is set, so items come out
+/// as `Result`s.
+pub struct Checked;
+
+/// `Diff` output: only items found on one side.
+pub struct Tags;
+
+/// `Diff` output: every item, with the matched pairs as `Merged::Both`.
+pub struct Pairs;
+
+/// Builder for a merge of two sorted inputs.
+///
+/// Each setting is tracked in the type, so an unconfigured diff runs the same
+/// code as `difference` and only checked diffs pay for the checks.
+pub struct Diff<Left, Right, C = Natural, Check = Unchecked, Output = Tags> {
+    left: Left,
+    right: Right,
+    compare: C,
+    duplicates: Duplicates,
+    validate: bool,
+    marker: PhantomData<(Check, Output)>,
+}
+
+impl<Left, Right> Diff<Left, Right>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+{
+    pub fn new<Lhs, Rhs>(left: Lhs, right: Rhs) -> Self
+    where
+        Lhs: IntoIterator<IntoIter = Left>,
+        Rhs: IntoIterator<IntoIter = Right>,
+    {
+        Diff {
+            left: left.into_iter(),
+            right: right.into_iter(),
+            compare: Natural,
+            duplicates: Duplicates::Multiset,
+            validate: false,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<Left, Right, C, Check, Output> Diff<Left, Right, C, Check, Output> {
+    /// Orders items with `compare`, which may be a closure over two items.
+    pub fn by<D>(self, compare: D) -> Diff<Left, Right, D, Check, Output> {
+        Diff {
+            left: self.left,
+            right: self.right,
+            compare,
+            duplicates: self.duplicates,
+            validate: self.validate,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn by_key<G>(self, key: G) -> Diff<Left, Right, ByKey<G>, Check, Output> {
+        self.by(ByKey(key))
+    }
+
+    pub fn by_cached_key<G>(self, key: G) -> Diff<Left, Right, ByCachedKey<G>, Check, Output> {
+        self.by(ByCachedKey(key))
+    }
+
+    /// Treats items equal under the comparator as duplicates per `policy`.
+    pub fn duplicates(self, policy: Duplicates) -> Diff<Left, Right, C, Checked, Output> {
+        let mut diff = self.retype();
+        diff.duplicates = policy;
+        diff
+    }
+
+    /// Verifies that both inputs are sorted under the comparator.
+    pub fn validate(self) -> Diff<Left, Right, C, Checked, Output> {
+        let mut diff = self.retype();
+        diff.validate = true;
+        diff
+    }
+
+    /// Emits the items found on both sides too.
+    pub fn pairs(self) -> Diff<Left, Right, C, Check, Pairs> {
+        self.retype()
+    }
+
+    fn retype<K, O>(self) -> Diff<Left, Right, C, K, O> {
+        Diff {
+            left: self.left,
+            right: self.right,
+            compare: self.compare,
+            duplicates: self.duplicates,
+            validate: self.validate,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<Left, Right, C> Diff<Left, Right, C, Unchecked, Tags>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    pub fn iter(self) -> SymDiffIter<Left, Right, C> {
+        SymDiffIter::new(self.left, self.right, self.compare)
+    }
+
+    pub fn for_each<F>(self, f: F)
+    where
+        F: FnMut(Tag<Left::Item>),
+    {
+        ::iter_difference_with(self.left, self.right, self.compare, f)
+    }
+}
+
+impl<Left, Right, C> Diff<Left, Right, C, Unchecked, Pairs>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    pub fn iter(self) -> MergeIter<Left, Right, C> {
+        MergeIter::new(self.left, self.right, self.compare)
+    }
+
+    pub fn for_each<F>(self, f: F)
+    where
+        F: FnMut(Merged<Left::Item>),
+    {
+        merge::merge_with(self.left, self.right, self.compare, f)
+    }
+}
+
+impl<Left, Right, C, Output> Diff<Left, Right, C, Unchecked, Output>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    /// Drives `visitor` through the merge, matched pairs included whatever
+    /// the output mode, and returns what its `finish` does.
+    pub fn visit<V>(self, visitor: &mut V) -> V::Output
+    where
+        V: DiffVisitor<Left::Item>,
+    {
+        merge::merge_with(self.left, self.right, self.compare, |item| {
+            dispatch(visitor, item)
+        });
+        visitor.finish()
+    }
+}
+
+impl<Left, Right, C, Output> Diff<Left, Right, C, Checked, Output>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item> + Clone,
+{
+    fn guards(self) -> (Guard<Left, C>, Guard<Right, C>, C) {
+        let (duplicates, validate) = (self.duplicates, self.validate);
+        let left = Guard::new(
+            self.left,
+            self.compare.clone(),
+            Side::Left,
+            duplicates,
+            validate,
+        );
+        let right = Guard::new(
+            self.right,
+            self.compare.clone(),
+            Side::Right,
+            duplicates,
+            validate,
+        );
+        (left, right, self.compare)
+    }
+
+    /// Drives `visitor` through the merge as `Diff::visit` does, stopping at
+    /// the first failed check.
+    pub fn visit<V>(self, visitor: &mut V) -> Result<V::Output, DiffError<Left::Item>>
+    where
+        V: DiffVisitor<Left::Item>,
+    {
+        let (left, right, compare) = self.guards();
+        for item in (CheckedMerge {
+            inner: MergeIter::new(left, right, compare),
+            failed: false,
+        }) {
+            dispatch(visitor, item?);
+        }
+        Ok(visitor.finish())
+    }
+}
+
+impl<Left, Right, C> Diff<Left, Right, C, Checked, Tags>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item> + Clone,
+{
+    pub fn iter(self) -> CheckedDiff<Left, Right, C> {
+        let (left, right, compare) = self.guards();
+        CheckedDiff {
+            inner: SymDiffIter::new(left, right, compare),
+            failed: false,
+        }
+    }
+
+    pub fn for_each<F>(self, mut f: F) -> Result<(), DiffError<Left::Item>>
+    where
+        F: FnMut(Tag<Left::Item>),
+    {
+        for item in self.iter() {
+            f(item?);
+        }
+        Ok(())
+    }
+}
+
+impl<Left, Right, C> Diff<Left, Right, C, Checked, Pairs>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item> + Clone,
+{
+    pub fn iter(self) -> CheckedMerge<Left, Right, C> {
+        let (left, right, compare) = self.guards();
+        CheckedMerge {
+            inner: MergeIter::new(left, right, compare),
+            failed: false,
+        }
+    }
+
+    pub fn for_each<F>(self, mut f: F) -> Result<(), DiffError<Left::Item>>
+    where
+        F: FnMut(Merged<Left::Item>),
+    {
+        for item in self.iter() {
+            f(item?);
+        }
+        Ok(())
+    }
+}
+
+fn dispatch<T, V: DiffVisitor<T>>(visitor: &mut V, item: Merged<T>) {
+    match item {
+        Merged::Left(item) => visitor.on_left(item),
+        Merged::Right(item) => visitor.on_right(item),
+        Merged::Both(left, right) => visitor.on_both(left, right),
+    }
+}
+
+/// A failed check of a `Diff`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiffError<T> {
+    Unsorted(UnsortedError<T>),
+    Duplicate(DuplicateError<T>),
+}
+
+impl<T> fmt::Display for DiffError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffError::Unsorted(e) => e.fmt(f),
+            DiffError::Duplicate(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<T: fmt::Debug + 'static> Error for DiffError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DiffError::Unsorted(e) => Some(e),
+            DiffError::Duplicate(e) => Some(e),
+        }
+    }
+}
+
+/// Input adapter applying the checks of a `Diff` under its comparator. A
+/// failure ends the side and leaves the error behind for the merge's owner.
+struct Guard<I: Iterator, C: Comparator<I::Item>> {
+    iter: I,
+    compare: C,
+    side: Side,
+    duplicates: Duplicates,
+    validate: bool,
+    next: Option<C::Entry>,
+    live: bool,
+    position: usize,
+    error: Option<DiffError<I::Item>>,
+    failed: bool,
+}
+
+impl<I, C> Guard<I, C>
+where
+    I: Iterator,
+    C: Comparator<I::Item>,
+{
+    fn new(iter: I, compare: C, side: Side, duplicates: Duplicates, validate: bool) -> Self {
+        Guard {
+            iter,
+            compare,
+            side,
+            duplicates,
+            validate,
+            next: None,
+            live: true,
+            position: 0,
+            error: None,
+            failed: false,
+        }
+    }
+
+    fn pull(&mut self) -> Option<C::Entry> {
+        if !self.live {
+            return None;
+        }
+        match self.iter.next() {
+            Some(item) => Some(self.compare.entry(item)),
+            None => {
+                self.live = false;
+                None
+            }
+        }
+    }
+
+    fn fail(&mut self, error: DiffError<I::Item>) {
+        self.error = Some(error);
+        self.live = false;
+    }
+
+    fn take_error(&mut self) -> Option<DiffError<I::Item>> {
+        if self.failed {
+            self.error.take()
+        } else {
+            None
+        }
+    }
+}
+
+impl<I, C> Iterator for Guard<I, C>
+where
+    I: Iterator,
+    C: Comparator<I::Item>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        if self.failed {
+            return None;
+        }
+
+        // With nothing to check, items pass straight through.
+        if self.duplicates == Duplicates::Multiset && !self.validate {
+            return self.iter.next();
+        }
+
+        let current = match self.next.take() {
+            Some(entry) => entry,
+            None => match self.pull() {
+                Some(entry) => entry,
+                None => {
+                    // A rejected duplicate is raised once the item before it
+                    // has been handed out.
+                    self.failed = self.error.is_some();
+                    return None;
+                }
+            },
+        };
+        self.position += 1;
+
+        while let Some(following) = self.pull() {
+            match self.compare.compare(&current, &following) {
+                Equal if self.duplicates == Duplicates::Dedup => self.position += 1,
+
+                Equal if self.duplicates == Duplicates::Reject => {
+                    self.fail(DiffError::Duplicate(DuplicateError {
+                        side: self.side,
+                        position: self.position,
+                        value: C::into_item(following),
+                    }));
+                }
+
+                Greater if self.validate => {
+                    self.fail(DiffError::Unsorted(UnsortedError {
+                        side: self.side,
+                        position: self.position,
+                        previous: C::into_item(current),
+                        next: C::into_item(following),
+                    }));
+                    self.failed = true;
+                    return None;
+                }
+
+                _ => {
+                    self.next = Some(following);
+                    break;
+                }
+            }
+        }
+
+        Some(C::into_item(current))
+    }
+}
+
+/// A merge over two guarded inputs.
+trait GuardedMerge<T>: Iterator {
+    /// The failure either side has raised, if any.
+    fn take_error(&mut self) -> Option<DiffError<T>>;
+}
+
+impl<Left, Right, C> GuardedMerge<Left::Item> for SymDiffIter<Guard<Left, C>, Guard<Right, C>, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    fn take_error(&mut self) -> Option<DiffError<Left::Item>> {
+        let (left, right) = self.sides_mut();
+        left.take_error().or_else(|| right.take_error())
+    }
+}
+
+impl<Left, Right, C> GuardedMerge<Left::Item> for MergeIter<Guard<Left, C>, Guard<Right, C>, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    fn take_error(&mut self) -> Option<DiffError<Left::Item>> {
+        let (left, right) = self.sides_mut();
+        left.take_error().or_else(|| right.take_error())
+    }
+}
+
+/// Steps a guarded merge. A side that fails reports itself as exhausted, so
+/// whatever the merge produced in the same step gives way to the error.
+fn checked_next<M, T>(inner: &mut M, failed: &mut bool) -> Option<Result<M::Item, DiffError<T>>>
+where
+    M: GuardedMerge<T>,
+{
+    if *failed {
+        return None;
+    }
+
+    let item = inner.next();
+    if let Some(error) = inner.take_error() {
+        *failed = true;
+        return Some(Err(error));
+    }
+
+    item.map(Ok)
+}
+
+/// Symmetric difference of a checked `Diff`.
+///
+/// The first failed check is yielded as an error, after which the iterator is
+/// exhausted.
+pub struct CheckedDiff<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    inner: SymDiffIter<Guard<Left, C>, Guard<Right, C>, C>,
+    failed: bool,
+}
+
+impl<Left, Right, C> Iterator for CheckedDiff<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    type Item = Result<Tag<Left::Item>, DiffError<Left::Item>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        checked_next(&mut self.inner, &mut self.failed)
+    }
+}
+
+/// Outer merge of a checked `Diff`, which ends like `CheckedDiff`.
+pub struct CheckedMerge<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    inner: MergeIter<Guard<Left, C>, Guard<Right, C>, C>,
+    failed: bool,
+}
+
+impl<Left, Right, C> Iterator for CheckedMerge<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    type Item = Result<Merged<Left::Item>, DiffError<Left::Item>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        checked_next(&mut self.inner, &mut self.failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+    use visit::Counts;
+    use SymmetricDifference;
+
+    #[test]
+    fn plain_diff_matches_difference() {
+        let left = [1, 2, 4, 5];
+        let right = [2, 3, 5];
+
+        let tags: Vec<_> = Diff::new(&left, &right).iter().map(Tag::unwrap).collect();
+        let plain: Vec<_> = (&left).difference(&right).map(Tag::unwrap).collect();
+        assert_eq!(tags, plain);
+
+        let mut both = Vec::new();
+        Diff::new(&left, &right).pairs().for_each(|item| {
+            if let Merged::Both(x, _) = item {
+                both.push(*x);
+            }
+        });
+        assert_eq!(both, vec![2, 5]);
+    }
+
+    #[test]
+    fn checks_follow_the_comparator() {
+        let left = [(1, 'a'), (1, 'b'), (3, 'c')];
+        let right = [(1, 'x'), (2, 'y'), (3, 'z')];
+
+        let keys: Result<Vec<_>, _> = Diff::new(&left, &right)
+            .by_key(|pair: &&(i32, char)| pair.0)
+            .duplicates(Duplicates::Dedup)
+            .validate()
+            .iter()
+            .map(|item| item.map(|tag| (tag.is_left(), tag.unwrap().1)))
+            .collect();
+        assert_eq!(keys, Ok(vec![(false, 'y')]));
+
+        let error = Diff::new(&left, &right)
+            .by_key(|pair: &&(i32, char)| pair.0)
+            .duplicates(Duplicates::Reject)
+            .for_each(|_| ())
+            .unwrap_err();
+        assert_eq!(
+            error,
+            DiffError::Duplicate(DuplicateError {
+                side: Side::Left,
+                position: 1,
+                value: &(1, 'b'),
+            })
+        );
+
+        // Sorted in the comparator's order, even though not in the natural one.
+        let failure = Diff::new(&[3, 2], &[1])
+            .by(|a: &&i32, b: &&i32| b.cmp(a))
+            .validate()
+            .pairs()
+            .iter()
+            .find_map(Result::err);
+        assert_eq!(failure, None);
+    }
+
+    #[test]
+    fn checked_visit_stops_at_first_failure() {
+        let counts = Diff::new(&[1, 2, 3], &[2, 4])
+            .validate()
+            .visit(&mut Counts::default());
+        assert_eq!(
+            counts,
+            Ok(Counts {
+                left: 2,
+                right: 1,
+                both: 1,
+            })
+        );
+
+        let error = Diff::new(&[1, 3, 2], &[2, 4])
+            .validate()
+            .visit(&mut Counts::default())
+            .unwrap_err();
+        assert_eq!(error.to_string(), "left input is not sorted at position 2");
+    }
+}
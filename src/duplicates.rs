@@ -0,0 +1,161 @@
+use core::error::Error;
+use core::fmt;
+
+use {CheckedDiff, Diff, DiffError, Natural, Side, Tag};
+
+/// How repeated items within a single input are treated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Copies are paired off one by one against the other side, so each side
+    /// reports its surplus copies. This is what `difference` does.
+    Multiset,
+    /// Runs of equal items are collapsed before the merge.
+    Dedup,
+    /// The first repeated item ends the diff with a `DuplicateError`.
+    Reject,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DuplicateError<T> {
+    pub side: Side,
+    pub position: usize,
+    pub value: T,
+}
+
+impl<T> fmt::Display for DuplicateError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "duplicate item at position {} of the {} input",
+            self.position, self.side
+        )
+    }
+}
+
+impl<T: fmt::Debug> Error for DuplicateError<T> {}
+
+/// Symmetric difference under an explicit `Duplicates` policy.
+///
+/// Only `Duplicates::Reject` ever yields an error, after which the iterator
+/// is exhausted.
+pub struct DuplicatesIter<Left, Right>
+where
+    Left: Iterator,
+    Left::Item: Ord,
+    Right: Iterator<Item = Left::Item>,
+{
+    inner: CheckedDiff<Left, Right, Natural>,
+}
+
+impl<Left, Right> DuplicatesIter<Left, Right>
+where
+    Left: Iterator,
+    Left::Item: Ord,
+    Right: Iterator<Item = Left::Item>,
+{
+    pub(crate) fn new(left: Left, right: Right, policy: Duplicates) -> Self {
+        DuplicatesIter {
+            inner: Diff::new(left, right).duplicates(policy).iter(),
+        }
+    }
+}
+
+impl<Left, Right> Iterator for DuplicatesIter<Left, Right>
+where
+    Left: Iterator,
+    Left::Item: Ord,
+    Right: Iterator<Item = Left::Item>,
+{
+    type Item = Result<Tag<Left::Item>, DuplicateError<Left::Item>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|item| item.map_err(duplicate))
+    }
+}
+
+/// The error of a diff that only polices duplicates.
+pub(crate) fn duplicate<T>(error: DiffError<T>) -> DuplicateError<T> {
+    match error {
+        DiffError::Duplicate(error) => error,
+        DiffError::Unsorted(_) => unreachable!("sorting is not validated"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+    use SymmetricDifference;
+
+    fn diff(
+        left: &[i32],
+        right: &[i32],
+        policy: Duplicates,
+    ) -> Vec<Result<(Side, i32), DuplicateError<i32>>> {
+        left.iter()
+            .cloned()
+            .difference_with_duplicates(right.iter().cloned(), policy)
+            .map(|item| {
+                item.map(|tag| match tag {
+                    Tag::Left(x) => (Side::Left, x),
+                    Tag::Right(x) => (Side::Right, x),
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn multiset_reports_surplus_copies() {
+        let left = &[1, 1, 1, 2];
+        let right = &[1, 2, 2];
+
+        assert_eq!(
+            diff(left, right, Duplicates::Multiset),
+            vec![
+                Ok((Side::Left, 1)),
+                Ok((Side::Left, 1)),
+                Ok((Side::Right, 2))
+            ]
+        );
+
+        let plain: Vec<_> = left.difference(right).map(|tag| *tag.unwrap()).collect();
+        assert_eq!(plain, vec![1, 1, 2]);
+    }
+
+    #[test]
+    fn dedup_gives_set_semantics() {
+        assert_eq!(
+            diff(&[1, 1, 3], &[1, 2, 2], Duplicates::Dedup),
+            vec![Ok((Side::Right, 2)), Ok((Side::Left, 3))]
+        );
+    }
+
+    #[test]
+    fn reject_reports_first_duplicate() {
+        let result = diff(&[0, 1, 2], &[1, 3, 3, 4], Duplicates::Reject);
+
+        assert_eq!(
+            result,
+            vec![
+                Ok((Side::Left, 0)),
+                Ok((Side::Left, 2)),
+                Ok((Side::Right, 3)),
+                Err(DuplicateError {
+                    side: Side::Right,
+                    position: 2,
+                    value: 3,
+                }),
+            ]
+        );
+
+        let mut seen = Vec::new();
+        let left: &[i32] = &[0, 1, 2];
+        let internal =
+            left.iter_difference_with_duplicates(&[1, 3, 3, 4], Duplicates::Reject, |tag| {
+                seen.push(*tag.unwrap())
+            });
+
+        assert_eq!(seen, vec![0, 2, 3]);
+        assert_eq!(internal.unwrap_err().position, 2);
+    }
+}
@@ -0,0 +1,173 @@
+use core::error::Error;
+use core::fmt;
+use core::marker::PhantomData;
+
+use {Natural, Side, SymDiffIter, Tag};
+
+/// An error yielded by one of the inputs of `try_difference`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InputError<E> {
+    pub side: Side,
+    pub error: E,
+}
+
+impl<E: fmt::Display> fmt::Display for InputError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} input failed: {}", self.side, self.error)
+    }
+}
+
+impl<E: Error + 'static> Error for InputError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+struct Unwrapped<I, E> {
+    iter: I,
+    error: Option<E>,
+}
+
+impl<I, T, E> Iterator for Unwrapped<I, E>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        match self.iter.next()? {
+            Ok(item) => Some(item),
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+}
+
+/// Symmetric difference of two inputs that yield `Result`.
+///
+/// Only `Ok` values are compared. The first `Err` from either side is yielded
+/// as an `InputError`, after which the iterator is exhausted.
+pub struct TryDiffIter<Left, Right, T, E>
+where
+    Left: Iterator<Item = Result<T, E>>,
+    Right: Iterator<Item = Result<T, E>>,
+    T: Ord,
+{
+    inner: SymDiffIter<Unwrapped<Left, E>, Unwrapped<Right, E>>,
+    failed: bool,
+    marker: PhantomData<T>,
+}
+
+impl<Left, Right, T, E> TryDiffIter<Left, Right, T, E>
+where
+    Left: Iterator<Item = Result<T, E>>,
+    Right: Iterator<Item = Result<T, E>>,
+    T: Ord,
+{
+    pub(crate) fn new(left: Left, right: Right) -> Self {
+        TryDiffIter {
+            inner: SymDiffIter::new(
+                Unwrapped {
+                    iter: left,
+                    error: None,
+                },
+                Unwrapped {
+                    iter: right,
+                    error: None,
+                },
+                Natural,
+            ),
+            failed: false,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<Left, Right, T, E> Iterator for TryDiffIter<Left, Right, T, E>
+where
+    Left: Iterator<Item = Result<T, E>>,
+    Right: Iterator<Item = Result<T, E>>,
+    T: Ord,
+{
+    type Item = Result<Tag<T>, InputError<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let item = self.inner.next();
+
+        let (left, right) = self.inner.sides_mut();
+        let error = match (left.error.take(), right.error.take()) {
+            (Some(error), _) => Some(InputError {
+                side: Side::Left,
+                error,
+            }),
+            (None, Some(error)) => Some(InputError {
+                side: Side::Right,
+                error,
+            }),
+            (None, None) => None,
+        };
+
+        if let Some(error) = error {
+            self.failed = true;
+            return Some(Err(error));
+        }
+
+        item.map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+    use SymmetricDifference;
+
+    fn parsed(lines: &[&str]) -> Vec<Result<i32, String>> {
+        lines
+            .iter()
+            .map(|line| line.parse().map_err(|_| format!("bad line {:?}", line)))
+            .collect()
+    }
+
+    #[test]
+    fn ok_values_are_compared() {
+        let left = parsed(&["1", "2", "4"]);
+        let right = parsed(&["2", "3"]);
+
+        let result: Result<Vec<_>, _> = left.try_difference(right).collect();
+        let values: Vec<_> = result.unwrap().into_iter().map(Tag::unwrap).collect();
+        assert_eq!(values, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn first_error_names_its_side() {
+        let left = parsed(&["1", "2", "4", "5"]);
+        let right = parsed(&["2", "3", "x", "6"]);
+
+        let mut seen = Vec::new();
+        let result = left.try_iter_difference(right, |tag| seen.push(tag.unwrap()));
+
+        assert_eq!(seen, vec![1, 3]);
+        let error = result.unwrap_err();
+        assert_eq!(error.side, Side::Right);
+        assert_eq!(error.to_string(), "right input failed: bad line \"x\"");
+
+        let left = parsed(&["y"]);
+        let error = left
+            .try_difference(parsed(&[]))
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.side, Side::Left);
+    }
+}
@@ -0,0 +1,59 @@
+//! Inputs shared by the test modules.
+
+use std::slice;
+
+pub static LEFT: &[i32] = &[1, 2, 4, 5, 6, 8, 13, 15, 16];
+pub static RIGHT: &[i32] = &[2, 3, 4, 5, 6, 7, 8];
+
+/// Panics if polled from either end after it has returned `None`.
+pub struct Tripwire<I> {
+    iter: I,
+    tripped: bool,
+}
+
+impl<I: Iterator> Iterator for Tripwire<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        assert!(!self.tripped, "polled after returning None");
+        let item = self.iter.next();
+        self.tripped = item.is_none();
+        item
+    }
+}
+
+impl<I: DoubleEndedIterator> DoubleEndedIterator for Tripwire<I> {
+    fn next_back(&mut self) -> Option<I::Item> {
+        assert!(!self.tripped, "polled after returning None");
+        let item = self.iter.next_back();
+        self.tripped = item.is_none();
+        item
+    }
+}
+
+pub fn tripwire(items: &[i32]) -> Tripwire<slice::Iter<'_, i32>> {
+    Tripwire {
+        iter: items.iter(),
+        tripped: false,
+    }
+}
+
+/// Yields `None` after every third item, then carries on.
+pub struct Stutter(i32);
+
+impl Iterator for Stutter {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        self.0 += 1;
+        if self.0 % 4 == 0 {
+            None
+        } else {
+            Some(self.0)
+        }
+    }
+}
+
+pub fn stutter() -> Stutter {
+    Stutter(0)
+}
@@ -0,0 +1,248 @@
+#[cfg(feature = "alloc")]
+use alloc::collections::btree_set::{self, BTreeSet};
+use core::cmp::Ordering::*;
+#[cfg(feature = "alloc")]
+use core::ops::Bound::{self, Excluded, Included, Unbounded};
+use core::slice;
+
+use Tag;
+
+/// Returns the number of leading items of `slice` less than `target`, using
+/// an exponential search from the front so that short runs stay cheap.
+pub fn gallop<T: Ord>(slice: &[T], target: &T) -> usize {
+    let mut bound = 1;
+    while bound < slice.len() && slice[bound] < *target {
+        bound *= 2;
+    }
+
+    let start = bound / 2;
+    let end = slice.len().min(bound + 1);
+    start + slice[start..end].partition_point(|item| item < target)
+}
+
+/// Symmetric difference of two sorted slices which skips over runs of
+/// unmatched items with `gallop` instead of comparing them one by one.
+///
+/// Suited to inputs of very different sizes; for similar sizes the linear
+/// merge of `difference` does fewer comparisons.
+pub struct Gallop<'a, T: 'a> {
+    left: &'a [T],
+    right: &'a [T],
+    run: Tag<slice::Iter<'a, T>>,
+}
+
+impl<'a, T: Ord> Iterator for Gallop<'a, T> {
+    type Item = Tag<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.run {
+                Tag::Left(ref mut run) => {
+                    if let Some(item) = run.next() {
+                        return Some(Tag::Left(item));
+                    }
+                }
+
+                Tag::Right(ref mut run) => {
+                    if let Some(item) = run.next() {
+                        return Some(Tag::Right(item));
+                    }
+                }
+            }
+
+            let (left, right) = (self.left, self.right);
+            match (left.first(), right.first()) {
+                (None, None) => return None,
+
+                (Some(_), None) => {
+                    self.run = Tag::Left(left.iter());
+                    self.left = &[];
+                }
+
+                (None, Some(_)) => {
+                    self.run = Tag::Right(right.iter());
+                    self.right = &[];
+                }
+
+                (Some(a), Some(b)) => match a.cmp(b) {
+                    Less => {
+                        let (run, rest) = left.split_at(gallop(left, b));
+                        self.run = Tag::Left(run.iter());
+                        self.left = rest;
+                    }
+
+                    Greater => {
+                        let (run, rest) = right.split_at(gallop(right, a));
+                        self.run = Tag::Right(run.iter());
+                        self.right = rest;
+                    }
+
+                    Equal => {
+                        self.left = &left[1..];
+                        self.right = &right[1..];
+                    }
+                },
+            }
+        }
+    }
+}
+
+pub fn gallop_difference<'a, T: Ord>(left: &'a [T], right: &'a [T]) -> Gallop<'a, T> {
+    Gallop {
+        left,
+        right,
+        run: Tag::Left([].iter()),
+    }
+}
+
+/// Symmetric difference of two `BTreeSet`s which locates the end of each run
+/// of unmatched items with a range query.
+///
+/// Every run costs a tree lookup, so this only pays off when one set is much
+/// smaller than the other.
+#[cfg(feature = "alloc")]
+pub struct GallopSet<'a, T: 'a> {
+    left: &'a BTreeSet<T>,
+    right: &'a BTreeSet<T>,
+    left_from: Bound<&'a T>,
+    right_from: Bound<&'a T>,
+    run: Option<Tag<btree_set::Range<'a, T>>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: Ord> Iterator for GallopSet<'a, T> {
+    type Item = Tag<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.run {
+                Some(Tag::Left(ref mut run)) => {
+                    if let Some(item) = run.next() {
+                        return Some(Tag::Left(item));
+                    }
+                }
+
+                Some(Tag::Right(ref mut run)) => {
+                    if let Some(item) = run.next() {
+                        return Some(Tag::Right(item));
+                    }
+                }
+
+                None => (),
+            }
+
+            let a = self.left.range((self.left_from, Unbounded)).next();
+            let b = self.right.range((self.right_from, Unbounded)).next();
+
+            match (a, b) {
+                (None, None) => return None,
+
+                (Some(_), None) => {
+                    self.run = Some(Tag::Left(self.left.range((self.left_from, Unbounded))));
+                    self.left_from = self.left.last().map_or(Unbounded, Excluded);
+                }
+
+                (None, Some(_)) => {
+                    self.run = Some(Tag::Right(self.right.range((self.right_from, Unbounded))));
+                    self.right_from = self.right.last().map_or(Unbounded, Excluded);
+                }
+
+                (Some(a), Some(b)) => match a.cmp(b) {
+                    Less => {
+                        self.run = Some(Tag::Left(self.left.range((self.left_from, Excluded(b)))));
+                        self.left_from = Included(b);
+                    }
+
+                    Greater => {
+                        self.run =
+                            Some(Tag::Right(self.right.range((self.right_from, Excluded(a)))));
+                        self.right_from = Included(a);
+                    }
+
+                    Equal => {
+                        self.left_from = Excluded(a);
+                        self.right_from = Excluded(b);
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub fn gallop_set_difference<'a, T: Ord>(
+    left: &'a BTreeSet<T>,
+    right: &'a BTreeSet<T>,
+) -> GallopSet<'a, T> {
+    GallopSet {
+        left,
+        right,
+        left_from: Unbounded,
+        right_from: Unbounded,
+        run: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+    use SymmetricDifference;
+
+    #[test]
+    fn gallop_counts_smaller_items() {
+        let slice: Vec<_> = (0..100).collect();
+
+        for target in -1..102 {
+            assert_eq!(
+                gallop(&slice, &target),
+                slice.partition_point(|&x| x < target)
+            );
+        }
+    }
+
+    #[test]
+    fn galloping_matches_linear_merge() {
+        let small = vec![3, 50, 51, 999, 1500];
+        let large: Vec<_> = (0..1000).filter(|x| x % 7 != 0).collect();
+
+        let expected: Vec<_> = small.iter().difference(&large).collect();
+        assert_eq!(
+            gallop_difference(&small, &large).collect::<Vec<_>>(),
+            expected
+        );
+
+        let expected: Vec<_> = large.iter().difference(&small).collect();
+        assert_eq!(
+            gallop_difference(&large, &small).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn set_galloping_matches_linear_merge() {
+        let small: BTreeSet<_> = [3, 50, 51, 999, 1500].iter().cloned().collect();
+        let large: BTreeSet<_> = (0..1000).filter(|x| x % 7 != 0).collect();
+
+        assert_eq!(
+            gallop_set_difference(&small, &large).collect::<Vec<_>>(),
+            small.iter().difference(&large).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            gallop_set_difference(&large, &small).collect::<Vec<_>>(),
+            large.iter().difference(&small).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn galloping_pairs_off_duplicates() {
+        let left = [1, 1, 1, 2];
+        let right = [1, 2, 2];
+
+        assert_eq!(
+            gallop_difference(&left, &right).collect::<Vec<_>>(),
+            left.iter().difference(&right).collect::<Vec<_>>()
+        );
+    }
+}
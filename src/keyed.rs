@@ -0,0 +1,174 @@
+use core::cmp::Ordering;
+
+use compare::Comparator;
+use merge::{MergeIter, Merged};
+
+/// Difference between an old (left) and a new (right) set of records.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change<T> {
+    Removed(T),
+    Added(T),
+    Changed { old: T, new: T },
+    Unchanged { old: T, new: T },
+}
+
+impl<T> Change<T> {
+    pub fn is_changed(&self) -> bool {
+        matches!(self, Change::Changed { .. })
+    }
+
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, Change::Unchanged { .. })
+    }
+}
+
+/// Keyed diff of two inputs sorted by key.
+///
+/// Records are matched by the comparator and the payloads of matched records
+/// are checked with a separate equality. Unchanged records are skipped unless
+/// asked for with `with_unchanged`.
+pub struct KeyedDiff<Left, Right, C, E>
+where
+    Left: Iterator,
+    Right: Iterator,
+    C: Comparator<Left::Item>,
+{
+    inner: MergeIter<Left, Right, C>,
+    eq: E,
+    unchanged: bool,
+}
+
+impl<Left, Right, C, E> KeyedDiff<Left, Right, C, E>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+    E: FnMut(&Left::Item, &Left::Item) -> bool,
+{
+    pub(crate) fn new(left: Left, right: Right, compare: C, eq: E) -> Self {
+        KeyedDiff {
+            inner: MergeIter::new(left, right, compare),
+            eq,
+            unchanged: false,
+        }
+    }
+
+    pub fn with_unchanged(mut self) -> Self {
+        self.unchanged = true;
+        self
+    }
+}
+
+impl<Left, Right, C, E> Iterator for KeyedDiff<Left, Right, C, E>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+    E: FnMut(&Left::Item, &Left::Item) -> bool,
+{
+    type Item = Change<Left::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next()? {
+                Merged::Left(old) => return Some(Change::Removed(old)),
+                Merged::Right(new) => return Some(Change::Added(new)),
+                Merged::Both(old, new) => {
+                    if !(self.eq)(&old, &new) {
+                        return Some(Change::Changed { old, new });
+                    }
+
+                    if self.unchanged {
+                        return Some(Change::Unchanged { old, new });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `KeyedDiff` over `(key, payload)` pairs.
+pub type RecordDiff<Left, Right, K, V> =
+    KeyedDiff<Left, Right, fn(&(K, V), &(K, V)) -> Ordering, fn(&(K, V), &(K, V)) -> bool>;
+
+pub(crate) fn by_record_key<K: Ord, V>(a: &(K, V), b: &(K, V)) -> Ordering {
+    a.0.cmp(&b.0)
+}
+
+pub(crate) fn record_payload_eq<K, V: PartialEq>(a: &(K, V), b: &(K, V)) -> bool {
+    a.1 == b.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+    use SymmetricDifference;
+
+    #[test]
+    fn records_are_matched_by_key() {
+        let old = vec![(1, "ann"), (2, "bob"), (3, "cy"), (5, "eve")];
+        let new = vec![(2, "bob"), (3, "cyd"), (4, "dee"), (5, "eve")];
+
+        let changes: Vec<_> = old.keyed_difference(new).collect();
+
+        assert_eq!(
+            changes,
+            vec![
+                Change::Removed((1, "ann")),
+                Change::Changed {
+                    old: (3, "cy"),
+                    new: (3, "cyd"),
+                },
+                Change::Added((4, "dee")),
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_records_on_request() {
+        struct Row {
+            id: u32,
+            name: &'static str,
+            seen: u32,
+        }
+
+        let old = vec![
+            Row {
+                id: 1,
+                name: "a",
+                seen: 10,
+            },
+            Row {
+                id: 2,
+                name: "b",
+                seen: 10,
+            },
+        ];
+        let new = vec![
+            Row {
+                id: 1,
+                name: "a",
+                seen: 20,
+            },
+            Row {
+                id: 2,
+                name: "c",
+                seen: 20,
+            },
+        ];
+
+        let changes: Vec<_> = old
+            .into_iter()
+            .keyed_difference_by_key(new, |row| row.id, |a, b| a.name == b.name)
+            .with_unchanged()
+            .map(|change| match change {
+                Change::Unchanged { old, new } => (false, old.seen, new.seen),
+                Change::Changed { old, new } => (true, old.seen, new.seen),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(changes, vec![(false, 10, 20), (true, 10, 20)]);
+    }
+}
@@ -0,0 +1,205 @@
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// Set of input indices holding a given item.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Membership(u64);
+
+impl Membership {
+    pub const MAX_INPUTS: usize = 64;
+
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    pub fn contains(self, index: usize) -> bool {
+        index < Self::MAX_INPUTS && self.0 & (1 << index) != 0
+    }
+
+    pub fn count(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn indices(self) -> Indices {
+        Indices(self.0)
+    }
+
+    fn insert(&mut self, index: usize) {
+        self.0 |= 1 << index;
+    }
+}
+
+pub struct Indices(u64);
+
+impl Iterator for Indices {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let index = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(index)
+    }
+}
+
+struct Head<T> {
+    item: T,
+    index: usize,
+}
+
+impl<T: Ord> Ord for Head<T> {
+    // Reversed so that `BinaryHeap` yields the smallest item first, and the
+    // lowest input index among equal items.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .item
+            .cmp(&self.item)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl<T: Ord> PartialOrd for Head<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> PartialEq for Head<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for Head<T> {}
+
+/// Merge of any number of sorted inputs.
+///
+/// Each distinct item is yielded once, taken from the lowest-indexed input
+/// holding it, along with the membership of every input it was found in.
+pub struct KMerge<I: Iterator> {
+    inputs: Vec<I>,
+    heap: BinaryHeap<Head<I::Item>>,
+}
+
+impl<I> Iterator for KMerge<I>
+where
+    I: Iterator,
+    I::Item: Ord,
+{
+    type Item = (I::Item, Membership);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Head { item, index } = self.heap.pop()?;
+
+        let mut membership = Membership::default();
+        membership.insert(index);
+
+        while let Some(head) = self.heap.peek() {
+            if head.item != item {
+                break;
+            }
+
+            membership.insert(head.index);
+            self.heap.pop();
+        }
+
+        for index in membership.indices() {
+            if let Some(next) = self.inputs[index].next() {
+                self.heap.push(Head { item: next, index });
+            }
+        }
+
+        Some((item, membership))
+    }
+}
+
+/// Merges up to `Membership::MAX_INPUTS` sorted inputs in a single pass.
+///
+/// # Panics
+///
+/// Panics if given more than `Membership::MAX_INPUTS` inputs.
+pub fn kmerge<I>(inputs: I) -> KMerge<<I::Item as IntoIterator>::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: IntoIterator,
+    <I::Item as IntoIterator>::Item: Ord,
+{
+    let mut inputs: Vec<_> = inputs.into_iter().map(IntoIterator::into_iter).collect();
+    assert!(
+        inputs.len() <= Membership::MAX_INPUTS,
+        "kmerge supports at most {} inputs",
+        Membership::MAX_INPUTS
+    );
+
+    let mut heap = BinaryHeap::with_capacity(inputs.len());
+    for (index, input) in inputs.iter_mut().enumerate() {
+        if let Some(item) = input.next() {
+            heap.push(Head { item, index });
+        }
+    }
+
+    KMerge { inputs, heap }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+
+    fn replicas() -> Vec<Vec<i32>> {
+        vec![vec![1, 2, 3, 5], vec![2, 3, 4], vec![3, 5, 6]]
+    }
+
+    #[test]
+    fn membership_is_reported_per_item() {
+        let merged: Vec<_> = kmerge(replicas())
+            .map(|(item, membership)| (item, membership.bits()))
+            .collect();
+
+        assert_eq!(
+            merged,
+            vec![
+                (1, 0b001),
+                (2, 0b011),
+                (3, 0b111),
+                (4, 0b010),
+                (5, 0b101),
+                (6, 0b100),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_queries_are_filters() {
+        let exactly = |k| -> Vec<i32> {
+            kmerge(replicas())
+                .filter(|&(_, membership)| membership.count() == k)
+                .map(|(item, _)| item)
+                .collect()
+        };
+
+        assert_eq!(exactly(1), vec![1, 4, 6]);
+        assert_eq!(exactly(2), vec![2, 5]);
+        assert_eq!(exactly(3), vec![3]);
+
+        let only_first_and_last: Vec<_> = kmerge(replicas())
+            .filter(|&(_, membership)| membership.bits() == 0b101)
+            .map(|(item, _)| item)
+            .collect();
+
+        assert_eq!(only_first_and_last, vec![5]);
+    }
+
+    #[test]
+    fn duplicates_within_an_input_are_not_collapsed() {
+        let merged: Vec<_> = kmerge(vec![vec![1, 1], vec![1]])
+            .map(|(item, membership)| (item, membership.bits()))
+            .collect();
+
+        assert_eq!(merged, vec![(1, 0b11), (1, 0b01)]);
+    }
+}
@@ -1,14 +1,352 @@
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+#[cfg(feature = "std")]
+use core::hash::Hash;
+use core::iter::FusedIterator;
+use core::ops::ControlFlow;
+
+mod checked;
+#[cfg(feature = "std")]
+mod codec;
+mod compare;
+mod delta;
+mod diff;
+mod duplicates;
+mod fallible;
+#[cfg(test)]
+mod fixtures;
+mod gallop;
+mod keyed;
+#[cfg(feature = "alloc")]
+mod kway;
+mod merge;
+#[cfg(feature = "std")]
+mod parallel;
+mod seek;
+mod setops;
+#[cfg(feature = "alloc")]
+mod simd;
+mod sorted;
+#[cfg(feature = "std")]
+mod unsorted;
+mod visit;
+
+pub use checked::{AssertSorted, CheckedIter, UnsortedError};
+#[cfg(feature = "std")]
+pub use codec::{encode, Decoder, Element, Encode, Encoder};
+pub use compare::{ByCachedKey, ByKey, ByValue, Comparator, Natural, Valued};
+pub use delta::{apply, compose, invert, Apply, Compose, Invert};
+pub use diff::{Checked, CheckedDiff, CheckedMerge, Diff, DiffError, Pairs, Tags, Unchecked};
+pub use duplicates::{DuplicateError, Duplicates, DuplicatesIter};
+pub use fallible::{InputError, TryDiffIter};
+pub use gallop::{gallop, gallop_difference, Gallop};
+#[cfg(feature = "alloc")]
+pub use gallop::{gallop_set_difference, GallopSet};
+pub use keyed::{Change, KeyedDiff, RecordDiff};
+#[cfg(feature = "alloc")]
+pub use kway::{kmerge, Indices, KMerge, Membership};
+pub use merge::{MergeIter, Merged};
+#[cfg(feature = "std")]
+pub use parallel::parallel_difference;
+#[cfg(feature = "alloc")]
+pub use seek::{key_cursor, set_cursor, KeyCursor, SetCursor};
+pub use seek::{seek_difference, seek_peekable, SeekDiff, SeekPeekable, SeekableSorted};
+pub use setops::{Difference, Intersection, Union};
+#[cfg(feature = "alloc")]
+pub use simd::{primitive_difference_into, Primitive};
+pub use sorted::{
+    iter_symmetric_difference, symmetric_difference, symmetric_difference_by, SortedIter,
+    SortedIterator, SortedSlice,
+};
+#[cfg(feature = "alloc")]
+pub use sorted::{SortedIntoIter, SortedVec};
+#[cfg(feature = "std")]
+pub use unsorted::{Order, UnsortedDiff};
+#[cfg(feature = "alloc")]
+pub use visit::Collect;
+#[cfg(feature = "std")]
+pub use visit::WriteLines;
+pub use visit::{Counts, DiffVisitor};
+
 pub trait SymmetricDifference: IntoIterator {
+    /// Repeated items are paired off one by one against the other side, so
+    /// `[1, 1]` against `[1]` yields `Left(1)`. See `Duplicates` for other
+    /// policies.
     fn difference<Rhs>(self, rhs: Rhs) -> SymDiffIter<Self::IntoIter, Rhs::IntoIter>
     where
         Self::Item: Eq + Ord,
         Rhs: IntoIterator<Item = Self::Item>;
 
+    fn difference_by<Rhs, C>(
+        self,
+        rhs: Rhs,
+        compare: C,
+    ) -> SymDiffIter<Self::IntoIter, Rhs::IntoIter, C>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        C: FnMut(&Self::Item, &Self::Item) -> Ordering;
+
+    fn difference_by_key<Rhs, G, K>(
+        self,
+        rhs: Rhs,
+        key: G,
+    ) -> SymDiffIter<Self::IntoIter, Rhs::IntoIter, ByKey<G>>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord;
+
+    fn difference_by_cached_key<Rhs, G, K>(
+        self,
+        rhs: Rhs,
+        key: G,
+    ) -> SymDiffIter<Self::IntoIter, Rhs::IntoIter, ByCachedKey<G>>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord;
+
     fn iter_difference<Rhs, F>(self, rhs: Rhs, f: F)
     where
         Self::Item: Eq + Ord,
         Rhs: IntoIterator<Item = Self::Item>,
         F: FnMut(Tag<Self::Item>);
+
+    /// Like `iter_difference`, but stops the merge as soon as `f` breaks and
+    /// returns the break value.
+    fn iter_difference_until<Rhs, B, F>(self, rhs: Rhs, f: F) -> ControlFlow<B>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Tag<Self::Item>) -> ControlFlow<B>;
+
+    /// Like `iter_difference`, but stops the merge at the first error from `f`
+    /// and returns it.
+    fn iter_difference_fallible<Rhs, E, F>(self, rhs: Rhs, f: F) -> Result<(), E>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Tag<Self::Item>) -> Result<(), E>;
+
+    fn iter_difference_by<Rhs, C, F>(self, rhs: Rhs, compare: C, f: F)
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        C: FnMut(&Self::Item, &Self::Item) -> Ordering,
+        F: FnMut(Tag<Self::Item>);
+
+    fn iter_difference_by_key<Rhs, G, K, F>(self, rhs: Rhs, key: G, f: F)
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord,
+        F: FnMut(Tag<Self::Item>);
+
+    fn iter_difference_by_cached_key<Rhs, G, K, F>(self, rhs: Rhs, key: G, f: F)
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord,
+        F: FnMut(Tag<Self::Item>);
+
+    fn difference_with_duplicates<Rhs>(
+        self,
+        rhs: Rhs,
+        policy: Duplicates,
+    ) -> DuplicatesIter<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>;
+
+    fn iter_difference_with_duplicates<Rhs, F>(
+        self,
+        rhs: Rhs,
+        policy: Duplicates,
+        f: F,
+    ) -> Result<(), DuplicateError<Self::Item>>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Tag<Self::Item>);
+
+    fn difference_checked<Rhs>(self, rhs: Rhs) -> CheckedIter<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>;
+
+    fn iter_difference_checked<Rhs, F>(
+        self,
+        rhs: Rhs,
+        f: F,
+    ) -> Result<(), UnsortedError<Self::Item>>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Tag<Self::Item>);
+
+    fn difference_debug_checked<Rhs>(
+        self,
+        rhs: Rhs,
+    ) -> SymDiffIter<AssertSorted<Self::IntoIter>, AssertSorted<Rhs::IntoIter>>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>;
+
+    fn try_difference<Rhs, V, E>(
+        self,
+        rhs: Rhs,
+    ) -> TryDiffIter<Self::IntoIter, Rhs::IntoIter, V, E>
+    where
+        Self: IntoIterator<Item = Result<V, E>>,
+        Rhs: IntoIterator<Item = Result<V, E>>,
+        V: Ord;
+
+    fn try_iter_difference<Rhs, V, E, F>(self, rhs: Rhs, f: F) -> Result<(), InputError<E>>
+    where
+        Self: IntoIterator<Item = Result<V, E>>,
+        Rhs: IntoIterator<Item = Result<V, E>>,
+        V: Ord,
+        F: FnMut(Tag<V>);
+
+    /// Symmetric difference of inputs in any order, matched by hashing.
+    #[cfg(feature = "std")]
+    fn unsorted_difference<Rhs>(self, rhs: Rhs, order: Order) -> UnsortedDiff<Self::IntoIter>
+    where
+        Self::Item: Hash + Eq,
+        Rhs: IntoIterator<Item = Self::Item>;
+
+    /// Like `unsorted_difference`, but yields both sides interleaved by value,
+    /// as `difference` does for sorted inputs.
+    #[cfg(feature = "std")]
+    fn unsorted_difference_sorted<Rhs>(self, rhs: Rhs) -> UnsortedDiff<Self::IntoIter>
+    where
+        Self::Item: Hash + Ord,
+        Rhs: IntoIterator<Item = Self::Item>;
+
+    fn keyed_difference<Rhs, K, V>(
+        self,
+        rhs: Rhs,
+    ) -> RecordDiff<Self::IntoIter, Rhs::IntoIter, K, V>
+    where
+        Self: IntoIterator<Item = (K, V)>,
+        Rhs: IntoIterator<Item = (K, V)>,
+        K: Ord,
+        V: PartialEq;
+
+    fn keyed_difference_by_key<Rhs, G, K, E>(
+        self,
+        rhs: Rhs,
+        key: G,
+        eq: E,
+    ) -> KeyedDiff<Self::IntoIter, Rhs::IntoIter, ByKey<G>, E>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord,
+        E: FnMut(&Self::Item, &Self::Item) -> bool;
+
+    fn outer_merge<Rhs>(self, rhs: Rhs) -> MergeIter<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>;
+
+    fn outer_merge_by<Rhs, C>(
+        self,
+        rhs: Rhs,
+        compare: C,
+    ) -> MergeIter<Self::IntoIter, Rhs::IntoIter, C>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        C: FnMut(&Self::Item, &Self::Item) -> Ordering;
+
+    fn outer_merge_by_key<Rhs, G, K>(
+        self,
+        rhs: Rhs,
+        key: G,
+    ) -> MergeIter<Self::IntoIter, Rhs::IntoIter, ByKey<G>>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord;
+
+    fn iter_outer_merge<Rhs, F>(self, rhs: Rhs, f: F)
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Merged<Self::Item>);
+
+    /// Drives `visitor` through the merge, calling `on_both` for the common
+    /// items as well, and returns what its `finish` does.
+    fn visit_difference<Rhs, V>(self, rhs: Rhs, visitor: &mut V) -> V::Output
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        V: DiffVisitor<Self::Item>;
+
+    /// Splits the difference into the items found only on the left and only on
+    /// the right, in one pass. Both are gathered in `Vec`s sized from the input
+    /// size hints, then moved into a default `A` and `B` with a single
+    /// `extend` each, so targets like `Vec` and `HashSet` reserve once.
+    #[cfg(feature = "alloc")]
+    fn partition_difference<Rhs, A, B>(self, rhs: Rhs) -> (A, B)
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        A: Default + Extend<Self::Item>,
+        B: Default + Extend<Self::Item>;
+
+    /// Like `partition_difference`, but appends to buffers the caller can
+    /// clear and pass in again, reserving room from the input size hints.
+    #[cfg(feature = "alloc")]
+    fn partition_difference_into<Rhs>(
+        self,
+        rhs: Rhs,
+        left_only: &mut Vec<Self::Item>,
+        right_only: &mut Vec<Self::Item>,
+    ) where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>;
+
+    fn sorted_union<Rhs>(self, rhs: Rhs) -> Union<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>;
+
+    fn sorted_intersection<Rhs>(self, rhs: Rhs) -> Intersection<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>;
+
+    fn sorted_difference<Rhs>(self, rhs: Rhs) -> Difference<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>;
+
+    fn iter_sorted_union<Rhs, F>(self, rhs: Rhs, f: F)
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Self::Item);
+
+    fn iter_sorted_intersection<Rhs, F>(self, rhs: Rhs, f: F)
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Self::Item);
+
+    fn iter_sorted_difference<Rhs, F>(self, rhs: Rhs, f: F)
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Self::Item);
 }
 
 impl<T: IntoIterator> SymmetricDifference for T {
@@ -17,71 +355,415 @@ impl<T: IntoIterator> SymmetricDifference for T {
         Self::Item: Eq + Ord,
         Rhs: IntoIterator<Item = Self::Item>,
     {
-        SymDiffIter {
-            left: self.into_iter(),
-            right: rhs.into_iter(),
-            rem: None,
+        Diff::new(self, rhs).iter()
+    }
+
+    fn difference_by<Rhs, C>(
+        self,
+        rhs: Rhs,
+        compare: C,
+    ) -> SymDiffIter<Self::IntoIter, Rhs::IntoIter, C>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        C: FnMut(&Self::Item, &Self::Item) -> Ordering,
+    {
+        Diff::new(self, rhs).by(compare).iter()
+    }
+
+    fn difference_by_key<Rhs, G, K>(
+        self,
+        rhs: Rhs,
+        key: G,
+    ) -> SymDiffIter<Self::IntoIter, Rhs::IntoIter, ByKey<G>>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord,
+    {
+        Diff::new(self, rhs).by_key(key).iter()
+    }
+
+    fn difference_by_cached_key<Rhs, G, K>(
+        self,
+        rhs: Rhs,
+        key: G,
+    ) -> SymDiffIter<Self::IntoIter, Rhs::IntoIter, ByCachedKey<G>>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord,
+    {
+        Diff::new(self, rhs).by_cached_key(key).iter()
+    }
+
+    fn iter_difference<Rhs, F>(self, rhs: Rhs, f: F)
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Tag<Self::Item>),
+    {
+        Diff::new(self, rhs).for_each(f)
+    }
+
+    fn iter_difference_until<Rhs, B, F>(self, rhs: Rhs, mut f: F) -> ControlFlow<B>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Tag<Self::Item>) -> ControlFlow<B>,
+    {
+        merge::try_merge_with(self, rhs, Natural, |item| match item {
+            Merged::Left(item) => f(Tag::Left(item)),
+            Merged::Right(item) => f(Tag::Right(item)),
+            Merged::Both(..) => ControlFlow::Continue(()),
+        })
+    }
+
+    fn iter_difference_fallible<Rhs, E, F>(self, rhs: Rhs, mut f: F) -> Result<(), E>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Tag<Self::Item>) -> Result<(), E>,
+    {
+        match self.iter_difference_until(rhs, |tag| match f(tag) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(e) => ControlFlow::Break(e),
+        }) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(e) => Err(e),
         }
     }
 
-    fn iter_difference<Rhs, F>(self, rhs: Rhs, mut f: F)
+    fn iter_difference_by<Rhs, C, F>(self, rhs: Rhs, compare: C, f: F)
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        C: FnMut(&Self::Item, &Self::Item) -> Ordering,
+        F: FnMut(Tag<Self::Item>),
+    {
+        Diff::new(self, rhs).by(compare).for_each(f)
+    }
+
+    fn iter_difference_by_key<Rhs, G, K, F>(self, rhs: Rhs, key: G, f: F)
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord,
+        F: FnMut(Tag<Self::Item>),
+    {
+        Diff::new(self, rhs).by_key(key).for_each(f)
+    }
+
+    fn iter_difference_by_cached_key<Rhs, G, K, F>(self, rhs: Rhs, key: G, f: F)
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord,
+        F: FnMut(Tag<Self::Item>),
+    {
+        Diff::new(self, rhs).by_cached_key(key).for_each(f)
+    }
+
+    fn difference_with_duplicates<Rhs>(
+        self,
+        rhs: Rhs,
+        policy: Duplicates,
+    ) -> DuplicatesIter<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+    {
+        DuplicatesIter::new(self.into_iter(), rhs.into_iter(), policy)
+    }
+
+    fn iter_difference_with_duplicates<Rhs, F>(
+        self,
+        rhs: Rhs,
+        policy: Duplicates,
+        f: F,
+    ) -> Result<(), DuplicateError<Self::Item>>
     where
         Self::Item: Eq + Ord,
         Rhs: IntoIterator<Item = Self::Item>,
         F: FnMut(Tag<Self::Item>),
     {
-        use std::cmp::Ordering::*;
+        // The multiset policy is what an unchecked diff does already.
+        if policy == Duplicates::Multiset {
+            Diff::new(self, rhs).for_each(f);
+            return Ok(());
+        }
 
-        let mut left = self.into_iter();
-        let mut right = rhs.into_iter();
+        Diff::new(self, rhs)
+            .duplicates(policy)
+            .for_each(f)
+            .map_err(duplicates::duplicate)
+    }
 
-        let mut curr_left = left.next();
-        let mut curr_right = right.next();
+    fn difference_checked<Rhs>(self, rhs: Rhs) -> CheckedIter<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+    {
+        CheckedIter::new(self.into_iter(), rhs.into_iter())
+    }
 
-        loop {
-            match (curr_left.take(), curr_right.take()) {
-                (None, None) => return,
-
-                (Some(item), None) => {
-                    f(Tag::Left(item));
-                    for item in left {
-                        f(Tag::Left(item));
-                    }
-                    return;
-                }
+    fn iter_difference_checked<Rhs, F>(
+        self,
+        rhs: Rhs,
+        f: F,
+    ) -> Result<(), UnsortedError<Self::Item>>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Tag<Self::Item>),
+    {
+        Diff::new(self, rhs)
+            .validate()
+            .for_each(f)
+            .map_err(checked::unsorted)
+    }
 
-                (None, Some(item)) => {
-                    f(Tag::Right(item));
-                    for item in right {
-                        f(Tag::Right(item));
-                    }
-                    return;
-                }
+    fn difference_debug_checked<Rhs>(
+        self,
+        rhs: Rhs,
+    ) -> SymDiffIter<AssertSorted<Self::IntoIter>, AssertSorted<Rhs::IntoIter>>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+    {
+        SymDiffIter::new(
+            AssertSorted::new(self.into_iter(), Side::Left),
+            AssertSorted::new(rhs.into_iter(), Side::Right),
+            Natural,
+        )
+    }
 
-                (Some(a), Some(b)) => match a.cmp(&b) {
-                    Greater => {
-                        f(Tag::Right(b));
-                        curr_left = Some(a);
-                        curr_right = right.next();
-                    }
-
-                    Less => {
-                        f(Tag::Left(a));
-                        curr_left = left.next();
-                        curr_right = Some(b);
-                    }
-
-                    Equal => {
-                        curr_left = left.next();
-                        curr_right = right.next();
-                    }
-                },
-            }
+    fn try_difference<Rhs, V, E>(self, rhs: Rhs) -> TryDiffIter<Self::IntoIter, Rhs::IntoIter, V, E>
+    where
+        Self: IntoIterator<Item = Result<V, E>>,
+        Rhs: IntoIterator<Item = Result<V, E>>,
+        V: Ord,
+    {
+        TryDiffIter::new(self.into_iter(), rhs.into_iter())
+    }
+
+    fn try_iter_difference<Rhs, V, E, F>(self, rhs: Rhs, mut f: F) -> Result<(), InputError<E>>
+    where
+        Self: IntoIterator<Item = Result<V, E>>,
+        Rhs: IntoIterator<Item = Result<V, E>>,
+        V: Ord,
+        F: FnMut(Tag<V>),
+    {
+        for item in self.try_difference(rhs) {
+            f(item?);
         }
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    fn unsorted_difference<Rhs>(self, rhs: Rhs, order: Order) -> UnsortedDiff<Self::IntoIter>
+    where
+        Self::Item: Hash + Eq,
+        Rhs: IntoIterator<Item = Self::Item>,
+    {
+        UnsortedDiff::new(self.into_iter(), rhs.into_iter(), order)
+    }
+
+    #[cfg(feature = "std")]
+    fn unsorted_difference_sorted<Rhs>(self, rhs: Rhs) -> UnsortedDiff<Self::IntoIter>
+    where
+        Self::Item: Hash + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+    {
+        UnsortedDiff::sorted(self.into_iter(), rhs.into_iter())
+    }
+
+    fn keyed_difference<Rhs, K, V>(
+        self,
+        rhs: Rhs,
+    ) -> RecordDiff<Self::IntoIter, Rhs::IntoIter, K, V>
+    where
+        Self: IntoIterator<Item = (K, V)>,
+        Rhs: IntoIterator<Item = (K, V)>,
+        K: Ord,
+        V: PartialEq,
+    {
+        KeyedDiff::new(
+            self.into_iter(),
+            rhs.into_iter(),
+            keyed::by_record_key,
+            keyed::record_payload_eq,
+        )
+    }
+
+    fn keyed_difference_by_key<Rhs, G, K, E>(
+        self,
+        rhs: Rhs,
+        key: G,
+        eq: E,
+    ) -> KeyedDiff<Self::IntoIter, Rhs::IntoIter, ByKey<G>, E>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord,
+        E: FnMut(&Self::Item, &Self::Item) -> bool,
+    {
+        KeyedDiff::new(self.into_iter(), rhs.into_iter(), ByKey(key), eq)
+    }
+
+    fn outer_merge<Rhs>(self, rhs: Rhs) -> MergeIter<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+    {
+        MergeIter::new(self.into_iter(), rhs.into_iter(), Natural)
+    }
+
+    fn outer_merge_by<Rhs, C>(
+        self,
+        rhs: Rhs,
+        compare: C,
+    ) -> MergeIter<Self::IntoIter, Rhs::IntoIter, C>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        C: FnMut(&Self::Item, &Self::Item) -> Ordering,
+    {
+        MergeIter::new(self.into_iter(), rhs.into_iter(), compare)
+    }
+
+    fn outer_merge_by_key<Rhs, G, K>(
+        self,
+        rhs: Rhs,
+        key: G,
+    ) -> MergeIter<Self::IntoIter, Rhs::IntoIter, ByKey<G>>
+    where
+        Rhs: IntoIterator<Item = Self::Item>,
+        G: FnMut(&Self::Item) -> K,
+        K: Ord,
+    {
+        MergeIter::new(self.into_iter(), rhs.into_iter(), ByKey(key))
+    }
+
+    fn iter_outer_merge<Rhs, F>(self, rhs: Rhs, f: F)
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Merged<Self::Item>),
+    {
+        merge::merge_with(self, rhs, Natural, f)
+    }
+
+    fn visit_difference<Rhs, V>(self, rhs: Rhs, visitor: &mut V) -> V::Output
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        V: DiffVisitor<Self::Item>,
+    {
+        Diff::new(self, rhs).visit(visitor)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn partition_difference<Rhs, A, B>(self, rhs: Rhs) -> (A, B)
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        A: Default + Extend<Self::Item>,
+        B: Default + Extend<Self::Item>,
+    {
+        let (mut left_buf, mut right_buf) = (Vec::new(), Vec::new());
+        self.partition_difference_into(rhs, &mut left_buf, &mut right_buf);
+
+        let (mut left_only, mut right_only) = (A::default(), B::default());
+        left_only.extend(left_buf);
+        right_only.extend(right_buf);
+        (left_only, right_only)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn partition_difference_into<Rhs>(
+        self,
+        rhs: Rhs,
+        left_only: &mut Vec<Self::Item>,
+        right_only: &mut Vec<Self::Item>,
+    ) where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+    {
+        let (lhs, rhs) = (self.into_iter(), rhs.into_iter());
+        // Each side can yield at most every item of its own input.
+        left_only.reserve(lhs.size_hint().0);
+        right_only.reserve(rhs.size_hint().0);
+        Diff::new(lhs, rhs).for_each(|tag| match tag {
+            Tag::Left(item) => left_only.push(item),
+            Tag::Right(item) => right_only.push(item),
+        })
+    }
+
+    fn sorted_union<Rhs>(self, rhs: Rhs) -> Union<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+    {
+        Union::new(self.into_iter(), rhs.into_iter(), Natural)
+    }
+
+    fn sorted_intersection<Rhs>(self, rhs: Rhs) -> Intersection<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+    {
+        Intersection::new(self.into_iter(), rhs.into_iter(), Natural)
+    }
+
+    fn sorted_difference<Rhs>(self, rhs: Rhs) -> Difference<Self::IntoIter, Rhs::IntoIter>
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+    {
+        Difference::new(self.into_iter(), rhs.into_iter(), Natural)
+    }
+
+    fn iter_sorted_union<Rhs, F>(self, rhs: Rhs, f: F)
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Self::Item),
+    {
+        setops::union_with(self, rhs, Natural, f)
+    }
+
+    fn iter_sorted_intersection<Rhs, F>(self, rhs: Rhs, f: F)
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Self::Item),
+    {
+        setops::intersection_with(self, rhs, Natural, f)
+    }
+
+    fn iter_sorted_difference<Rhs, F>(self, rhs: Rhs, f: F)
+    where
+        Self::Item: Eq + Ord,
+        Rhs: IntoIterator<Item = Self::Item>,
+        F: FnMut(Self::Item),
+    {
+        setops::difference_with(self, rhs, Natural, f)
     }
 }
 
-#[derive(Debug)]
+fn iter_difference_with<Lhs, Rhs, C, F>(lhs: Lhs, rhs: Rhs, compare: C, mut f: F)
+where
+    Lhs: IntoIterator,
+    Rhs: IntoIterator<Item = Lhs::Item>,
+    C: Comparator<Lhs::Item>,
+    F: FnMut(Tag<Lhs::Item>),
+{
+    merge::merge_with(lhs, rhs, compare, |item| match item {
+        Merged::Left(item) => f(Tag::Left(item)),
+        Merged::Right(item) => f(Tag::Right(item)),
+        Merged::Both(..) => (),
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Tag<T> {
     Left(T),
     Right(T),
@@ -101,89 +783,156 @@ impl<T> Tag<T> {
     }
 
     pub fn is_left(&self) -> bool {
+        matches!(self, Tag::Left(_))
+    }
+
+    pub fn is_right(&self) -> bool {
+        matches!(self, Tag::Right(_))
+    }
+
+    pub fn invert(self) -> Tag<T> {
         match self {
-            Tag::Left(_) => true,
-            _ => false,
+            Tag::Left(x) => Tag::Right(x),
+            Tag::Right(x) => Tag::Left(x),
         }
     }
+}
 
-    pub fn is_right(&self) -> bool {
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Tag::Right(_) => true,
-            _ => false,
+            Side::Left => f.write_str("left"),
+            Side::Right => f.write_str("right"),
         }
     }
 }
 
-pub struct SymDiffIter<Left, Right>
+pub struct SymDiffIter<Left, Right, C = Natural>
 where
     Left: Iterator,
     Right: Iterator,
+    C: Comparator<Left::Item>,
+{
+    inner: MergeIter<Left, Right, C>,
+}
+
+impl<Left, Right, C> SymDiffIter<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
 {
-    left: Left,
-    right: Right,
-    rem: Option<Tag<Left::Item>>,
+    fn new(left: Left, right: Right, compare: C) -> Self {
+        SymDiffIter {
+            inner: MergeIter::new(left, right, compare),
+        }
+    }
+
+    fn sides_mut(&mut self) -> (&mut Left, &mut Right) {
+        self.inner.sides_mut()
+    }
 }
 
-impl<Left, Right> Iterator for SymDiffIter<Left, Right>
+impl<Left, Right, C> Iterator for SymDiffIter<Left, Right, C>
 where
     Left: Iterator,
-    Left::Item: Eq + Ord,
     Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
 {
     type Item = Tag<Left::Item>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        use std::cmp::Ordering::*;
+        loop {
+            match self.inner.next()? {
+                Merged::Left(item) => return Some(Tag::Left(item)),
+                Merged::Right(item) => return Some(Tag::Right(item)),
+                Merged::Both(..) => (),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let ((left_lo, left_hi), (right_lo, right_hi)) = self.inner.side_hints();
 
-        let (mut left, mut right) = match self.rem.take() {
-            None => (self.left.next(), self.right.next()),
-            Some(Tag::Left(rem)) => (Some(rem), self.right.next()),
-            Some(Tag::Right(rem)) => (self.left.next(), Some(rem)),
+        // Every item on one side can cancel at most one item on the other.
+        let lower = match (left_hi, right_hi) {
+            (Some(left_hi), Some(right_hi)) => left_lo
+                .saturating_sub(right_hi)
+                .max(right_lo.saturating_sub(left_hi)),
+            (Some(left_hi), None) => right_lo.saturating_sub(left_hi),
+            (None, Some(right_hi)) => left_lo.saturating_sub(right_hi),
+            (None, None) => 0,
         };
 
+        (lower, self.inner.size_hint().1)
+    }
+
+    // `try_fold` cannot be overridden on stable; `iter_difference_until` is
+    // the early-exit equivalent.
+    fn fold<B, G>(self, init: B, mut g: G) -> B
+    where
+        G: FnMut(B, Self::Item) -> B,
+    {
+        self.inner.fold(init, |acc, item| match item {
+            Merged::Left(item) => g(acc, Tag::Left(item)),
+            Merged::Right(item) => g(acc, Tag::Right(item)),
+            Merged::Both(..) => acc,
+        })
+    }
+
+    fn for_each<G>(self, mut g: G)
+    where
+        G: FnMut(Self::Item),
+    {
+        self.fold((), |(), item| g(item))
+    }
+}
+
+impl<Left, Right, C> DoubleEndedIterator for SymDiffIter<Left, Right, C>
+where
+    Left: DoubleEndedIterator,
+    Right: DoubleEndedIterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
         loop {
-            match (left.take(), right.take()) {
-                (Some(left), None) => return Some(Tag::Left(left)),
-                (None, Some(right)) => return Some(Tag::Right(right)),
-                (Some(left), Some(right)) => match left.cmp(&right) {
-                    Greater => {
-                        self.rem = Some(Tag::Left(left));
-                        return Some(Tag::Right(right));
-                    }
-
-                    Less => {
-                        self.rem = Some(Tag::Right(right));
-                        return Some(Tag::Left(left));
-                    }
-
-                    _ => (),
-                },
-
-                _ => return None,
+            match self.inner.next_back()? {
+                Merged::Left(item) => return Some(Tag::Left(item)),
+                Merged::Right(item) => return Some(Tag::Right(item)),
+                Merged::Both(..) => (),
             }
-
-            left = self.left.next();
-            right = self.right.next();
         }
     }
 }
 
+impl<Left, Right, C> FusedIterator for SymDiffIter<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fixtures::{LEFT, RIGHT};
     use std::collections::HashSet;
-
-    static LEFT: &[i32] = &[1, 2, 4, 5, 6, 8, 13, 15, 16];
-    static RIGHT: &[i32] = &[2, 3, 4, 5, 6, 7, 8];
+    use std::prelude::v1::*;
 
     #[test]
     fn diff_works() {
         let set: HashSet<_> = LEFT.difference(RIGHT).map(Tag::unwrap).collect();
         let expected_diff: HashSet<_> = {
-            let left: HashSet<_> = LEFT.into_iter().collect();
-            let right: HashSet<_> = RIGHT.into_iter().collect();
-            left.symmetric_difference(&right).map(|&x| x).collect()
+            let left: HashSet<_> = LEFT.iter().collect();
+            let right: HashSet<_> = RIGHT.iter().collect();
+            left.symmetric_difference(&right).copied().collect()
         };
 
         assert_eq!(set, expected_diff);
@@ -198,11 +947,188 @@ mod tests {
         });
 
         let expected_diff: HashSet<_> = {
-            let left: HashSet<_> = LEFT.into_iter().collect();
-            let right: HashSet<_> = RIGHT.into_iter().collect();
-            left.symmetric_difference(&right).map(|&x| x).collect()
+            let left: HashSet<_> = LEFT.iter().collect();
+            let right: HashSet<_> = RIGHT.iter().collect();
+            left.symmetric_difference(&right).copied().collect()
         };
 
         assert_eq!(set, expected_diff);
     }
+
+    #[test]
+    fn iter_difference_until_stops_early() {
+        let mut pulled = 0;
+        let mut found = Vec::new();
+        let flow = LEFT
+            .iter()
+            .inspect(|_| pulled += 1)
+            .iter_difference_until(RIGHT, |tag| {
+                found.push(*tag.unwrap());
+                if found.len() == 3 {
+                    ControlFlow::Break(found.len())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+
+        assert_eq!(flow, ControlFlow::Break(3));
+        assert_eq!(found, vec![1, 3, 7]);
+        assert!(pulled < LEFT.len());
+
+        let mut written = 0;
+        let result = LEFT.iter_difference_fallible(RIGHT, |_| {
+            if written == 5 {
+                return Err("sink is full");
+            }
+            written += 1;
+            Ok(())
+        });
+        assert_eq!(result, Err("sink is full"));
+        assert_eq!(written, 5);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn partition_difference_splits_sides() {
+        let (left, right): (Vec<&i32>, HashSet<&i32>) = LEFT.partition_difference(RIGHT);
+        assert_eq!(left, vec![&1, &13, &15, &16]);
+        assert_eq!(right, [&3, &7].iter().cloned().collect());
+
+        let (mut left_only, mut right_only) = (Vec::new(), Vec::new());
+        for _ in 0..2 {
+            left_only.clear();
+            right_only.clear();
+            RIGHT.partition_difference_into(LEFT, &mut left_only, &mut right_only);
+            assert_eq!(left_only, vec![&3, &7]);
+            assert_eq!(right_only, vec![&1, &13, &15, &16]);
+        }
+    }
+
+    #[test]
+    fn fold_resumes_where_next_left_off() {
+        let total = LEFT.difference(RIGHT).count();
+
+        for front in 0..=total {
+            for back in 0..=total - front {
+                let mut iter = LEFT.difference(RIGHT);
+                iter.by_ref().take(front).for_each(drop);
+                iter.by_ref().rev().take(back).for_each(drop);
+
+                let mut stepped = Vec::new();
+                let mut rest = LEFT.difference(RIGHT);
+                rest.by_ref().take(front).for_each(drop);
+                rest.by_ref().rev().take(back).for_each(drop);
+                // A `for` loop steps with `next`, not `fold`.
+                for tag in rest {
+                    stepped.push(tag);
+                }
+
+                let folded = iter.fold(Vec::new(), |mut acc, tag| {
+                    acc.push(tag);
+                    acc
+                });
+                assert_eq!(folded, stepped);
+            }
+        }
+    }
+
+    #[test]
+    fn difference_by_key_compares_keys_only() {
+        let left = &[(1, "a"), (2, "b"), (4, "d")];
+        let right = &[(2, "B"), (3, "C"), (4, "D")];
+
+        let diff: Vec<_> = left
+            .iter()
+            .difference_by_key(right, |&&(id, _)| id)
+            .map(|tag| (tag.is_left(), tag.unwrap().1))
+            .collect();
+
+        assert_eq!(diff, &[(true, "a"), (false, "C")]);
+    }
+
+    #[test]
+    fn by_variants_agree() {
+        let reversed = |a: &&i32, b: &&i32| b.cmp(a);
+        let left: Vec<_> = LEFT.iter().rev().collect();
+        let right: Vec<_> = RIGHT.iter().rev().collect();
+
+        let mut calls = 0;
+        let by: Vec<_> = left
+            .iter()
+            .cloned()
+            .difference_by(right.iter().cloned(), reversed)
+            .map(Tag::unwrap)
+            .collect();
+        let by_key: Vec<_> = left
+            .iter()
+            .cloned()
+            .difference_by_key(right.iter().cloned(), |&&x| -x)
+            .map(Tag::unwrap)
+            .collect();
+        let by_cached_key: Vec<_> = left
+            .iter()
+            .cloned()
+            .difference_by_cached_key(right.iter().cloned(), |&&x| {
+                calls += 1;
+                -x
+            })
+            .map(Tag::unwrap)
+            .collect();
+
+        let mut internal = Vec::new();
+        left.iter().cloned().iter_difference_by_cached_key(
+            right.iter().cloned(),
+            |&&x| -x,
+            |x| internal.push(x.unwrap()),
+        );
+
+        assert_eq!(by, by_key);
+        assert_eq!(by, by_cached_key);
+        assert_eq!(by, internal);
+        assert_eq!(calls, left.len() + right.len());
+    }
+
+    #[test]
+    fn size_hint_bounds_the_output() {
+        let cases: &[(&[i32], &[i32])] = &[
+            (LEFT, RIGHT),
+            (RIGHT, LEFT),
+            (&[], RIGHT),
+            (LEFT, &[]),
+            (&[1, 2, 3], &[1, 2, 3]),
+        ];
+
+        for &(left, right) in cases {
+            let total = left.difference(right).count();
+            let mut iter = left.difference(right);
+
+            for consumed in 0..=total {
+                let (lower, upper) = iter.size_hint();
+                let remaining = total - consumed;
+                assert!(lower <= remaining && remaining <= upper.unwrap());
+                iter.next();
+            }
+        }
+    }
+
+    #[test]
+    fn largest_differences_via_rev() {
+        let largest: Vec<_> = LEFT
+            .difference(RIGHT)
+            .rev()
+            .take(3)
+            .map(Tag::unwrap)
+            .collect();
+        assert_eq!(largest, vec![&16, &15, &13]);
+
+        let smallest: Vec<_> = LEFT
+            .iter()
+            .rev()
+            .difference_by(RIGHT.iter().rev(), |a, b| b.cmp(a))
+            .rev()
+            .take(2)
+            .map(Tag::unwrap)
+            .collect();
+        assert_eq!(smallest, vec![&1, &3]);
+    }
 }
@@ -0,0 +1,269 @@
+extern crate symdiff;
+
+use std::cell::RefCell;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process;
+use std::rc::Rc;
+
+use symdiff::{Merged, SymmetricDifference, Tag};
+
+const USAGE: &str = "\
+usage: symdiff [-123] [--] FILE1 FILE2
+
+Compares two files sorted in byte order, line by line. Column one holds lines
+only in FILE1, column two lines only in FILE2 and column three lines in both.
+A FILE of - reads standard input, and -- ends the options.
+
+    -1    suppress column one
+    -2    suppress column two
+    -3    suppress column three
+
+Exits with 0 if the files are identical, 1 if they differ and 2 on error,
+including when either file is not sorted.";
+
+struct Options {
+    show: [bool; 3],
+    paths: Vec<String>,
+}
+
+/// Returns `None` when help is asked for.
+fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        show: [true; 3],
+        paths: Vec::new(),
+    };
+
+    let mut options_ended = false;
+    for arg in args {
+        if options_ended {
+            options.paths.push(arg);
+            continue;
+        }
+
+        if arg == "--" {
+            options_ended = true;
+        } else if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        } else if arg.len() > 1 && arg.starts_with('-') {
+            for flag in arg[1..].chars() {
+                match flag {
+                    '1' => options.show[0] = false,
+                    '2' => options.show[1] = false,
+                    '3' => options.show[2] = false,
+                    _ => return Err(format!("unknown option -{}\n\n{}", flag, USAGE)),
+                }
+            }
+        } else {
+            options.paths.push(arg);
+        }
+    }
+
+    if options.paths.len() != 2 {
+        return Err(USAGE.to_owned());
+    }
+    if options.paths[0] == "-" && options.paths[1] == "-" {
+        return Err("only one FILE can be standard input".to_owned());
+    }
+    Ok(Some(options))
+}
+
+type ErrorSlot = Rc<RefCell<Option<String>>>;
+
+/// Lines of one input as raw bytes, without the newline. They end early and
+/// leave a message in `error` on a read failure or a line smaller than the
+/// one before it.
+struct SortedLines {
+    reader: Box<dyn BufRead>,
+    path: String,
+    number: usize,
+    previous: Option<Vec<u8>>,
+    error: ErrorSlot,
+}
+
+impl SortedLines {
+    fn open(path: &str, error: ErrorSlot) -> Result<Self, String> {
+        let reader: Box<dyn BufRead> = if path == "-" {
+            Box::new(BufReader::new(io::stdin()))
+        } else {
+            let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+            Box::new(BufReader::new(file))
+        };
+
+        Ok(SortedLines {
+            reader,
+            path: path.to_owned(),
+            number: 0,
+            previous: None,
+            error,
+        })
+    }
+
+    fn fail(&mut self, message: String) -> Option<Vec<u8>> {
+        let mut error = self.error.borrow_mut();
+        if error.is_none() {
+            *error = Some(message);
+        }
+        None
+    }
+}
+
+impl Iterator for SortedLines {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.error.borrow().is_some() {
+            return None;
+        }
+
+        let mut line = Vec::new();
+        match self.reader.read_until(b'\n', &mut line) {
+            Ok(0) => return None,
+            Ok(_) => {
+                if line.last() == Some(&b'\n') {
+                    line.pop();
+                }
+            }
+            Err(e) => {
+                let message = format!("{}: {}", self.path, e);
+                return self.fail(message);
+            }
+        }
+        self.number += 1;
+
+        if self
+            .previous
+            .as_ref()
+            .is_some_and(|previous| line < *previous)
+        {
+            let message = format!("{}: line {} is not in sorted order", self.path, self.number);
+            return self.fail(message);
+        }
+        self.previous = Some(line.clone());
+        Some(line)
+    }
+}
+
+/// Writes the visible columns, indenting each by one tab per visible column
+/// to its left, and reports whether any line was found in only one input.
+fn write_rows<I, W>(rows: I, show: [bool; 3], out: &mut W) -> io::Result<bool>
+where
+    I: Iterator<Item = Merged<Vec<u8>>>,
+    W: Write,
+{
+    let mut differ = false;
+    for row in rows {
+        let (column, line) = match row {
+            Merged::Left(line) => (0, line),
+            Merged::Right(line) => (1, line),
+            Merged::Both(line, _) => (2, line),
+        };
+        differ |= column != 2;
+
+        if show[column] {
+            for _ in show[..column].iter().filter(|&&shown| shown) {
+                out.write_all(b"\t")?;
+            }
+            out.write_all(&line)?;
+            out.write_all(b"\n")?;
+        }
+    }
+    Ok(differ)
+}
+
+fn run() -> Result<bool, String> {
+    let options = match parse_args(env::args().skip(1))? {
+        Some(options) => options,
+        None => {
+            println!("{}", USAGE);
+            return Ok(false);
+        }
+    };
+    let error = ErrorSlot::default();
+    let left = SortedLines::open(&options.paths[0], error.clone())?;
+    let right = SortedLines::open(&options.paths[1], error.clone())?;
+
+    // Common lines only matter for the third column; without it the plain
+    // symmetric difference is enough.
+    let rows: Box<dyn Iterator<Item = Merged<Vec<u8>>>> = if options.show[2] {
+        Box::new(left.outer_merge(right))
+    } else {
+        Box::new(left.difference(right).map(|tag| match tag {
+            Tag::Left(line) => Merged::Left(line),
+            Tag::Right(line) => Merged::Right(line),
+        }))
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let differ = write_rows(rows, options.show, &mut out)
+        .and_then(|differ| out.flush().map(|_| differ))
+        .map_err(|e| e.to_string())?;
+
+    let error = error.borrow_mut().take();
+    match error {
+        Some(message) => Err(message),
+        None => Ok(differ),
+    }
+}
+
+fn main() {
+    let code = match run() {
+        Ok(false) => 0,
+        Ok(true) => 1,
+        Err(message) => {
+            eprintln!("symdiff: {}", message);
+            2
+        }
+    };
+    process::exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Option<Options>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn lines(lines: &[&[u8]]) -> Vec<Vec<u8>> {
+        lines.iter().map(|line| line.to_vec()).collect()
+    }
+
+    #[test]
+    fn flags_suppress_columns() {
+        let options = args(&["-13", "a", "-"]).ok().unwrap().unwrap();
+        assert_eq!(options.show, [false, true, false]);
+        assert_eq!(options.paths, vec!["a", "-"]);
+
+        assert!(args(&["-4", "a", "b"]).is_err());
+        assert!(args(&["a"]).is_err());
+        assert!(args(&["-", "-"]).is_err());
+        assert!(args(&["a", "--help"]).ok().unwrap().is_none());
+
+        let options = args(&["-3", "--", "-1", "--help"]).ok().unwrap().unwrap();
+        assert_eq!(options.show, [true, true, false]);
+        assert_eq!(options.paths, vec!["-1", "--help"]);
+    }
+
+    #[test]
+    fn rows_are_laid_out_like_comm() {
+        let left = lines(&[b"a", b"b", b"d"]);
+        let right = lines(&[b"b", b"c"]);
+
+        let mut out = Vec::new();
+        let differ = write_rows(left.outer_merge(right), [true; 3], &mut out).unwrap();
+        assert!(differ);
+        assert_eq!(out, b"a\n\t\tb\n\tc\nd\n");
+
+        let left = lines(&[b"b", b"\xff"]);
+        let right = lines(&[b"b", b"\xff"]);
+
+        let mut out = Vec::new();
+        let differ = write_rows(left.outer_merge(right), [false, true, true], &mut out).unwrap();
+        assert!(!differ);
+        assert_eq!(out, b"\tb\n\t\xff\n");
+    }
+}
@@ -0,0 +1,612 @@
+use core::cmp::Ordering::*;
+use core::convert::Infallible;
+use core::iter::FusedIterator;
+use core::ops::ControlFlow;
+
+use compare::{Comparator, Natural};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Merged<T> {
+    Left(T),
+    Right(T),
+    Both(T, T),
+}
+
+impl<T> Merged<T> {
+    pub fn left(&self) -> Option<&T> {
+        match self {
+            Merged::Left(x) | Merged::Both(x, _) => Some(x),
+            Merged::Right(_) => None,
+        }
+    }
+
+    pub fn right(&self) -> Option<&T> {
+        match self {
+            Merged::Right(x) | Merged::Both(_, x) => Some(x),
+            Merged::Left(_) => None,
+        }
+    }
+
+    pub fn is_left(&self) -> bool {
+        matches!(self, Merged::Left(_))
+    }
+
+    pub fn is_right(&self) -> bool {
+        matches!(self, Merged::Right(_))
+    }
+
+    pub fn is_both(&self) -> bool {
+        matches!(self, Merged::Both(..))
+    }
+}
+
+/// Which inputs may still be polled. A side leaves the state the first time
+/// it returns `None` and is never polled again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum State {
+    BothLive,
+    LeftOnly,
+    RightOnly,
+    Done,
+}
+
+impl State {
+    #[inline]
+    pub(crate) fn left_live(self) -> bool {
+        matches!(self, State::BothLive | State::LeftOnly)
+    }
+
+    #[inline]
+    pub(crate) fn right_live(self) -> bool {
+        matches!(self, State::BothLive | State::RightOnly)
+    }
+
+    pub(crate) fn end_left(self) -> State {
+        match self {
+            State::BothLive | State::RightOnly => State::RightOnly,
+            State::LeftOnly | State::Done => State::Done,
+        }
+    }
+
+    pub(crate) fn end_right(self) -> State {
+        match self {
+            State::BothLive | State::LeftOnly => State::LeftOnly,
+            State::RightOnly | State::Done => State::Done,
+        }
+    }
+}
+
+/// Full outer merge of two sorted inputs.
+///
+/// Unlike `SymDiffIter`, items found on both sides are not discarded but
+/// reported together as `Merged::Both(left, right)`.
+///
+/// Each input is polled until it first returns `None` and never again, so
+/// inputs need not be fused.
+pub struct MergeIter<Left, Right, C = Natural>
+where
+    Left: Iterator,
+    Right: Iterator,
+    C: Comparator<Left::Item>,
+{
+    left: Left,
+    right: Right,
+    compare: C,
+    state: State,
+    // The next item from each end of each side, compared in place so that a
+    // pending item is never moved between steps.
+    left_front: Option<C::Entry>,
+    right_front: Option<C::Entry>,
+    left_back: Option<C::Entry>,
+    right_back: Option<C::Entry>,
+}
+
+impl<Left, Right, C> MergeIter<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    pub(crate) fn new(left: Left, right: Right, compare: C) -> Self {
+        MergeIter {
+            left,
+            right,
+            compare,
+            state: State::BothLive,
+            left_front: None,
+            right_front: None,
+            left_back: None,
+            right_back: None,
+        }
+    }
+
+    pub(crate) fn sides_mut(&mut self) -> (&mut Left, &mut Right) {
+        (&mut self.left, &mut self.right)
+    }
+
+    /// Size hints of what remains on each side, including pending items.
+    pub(crate) fn side_hints(&self) -> ((usize, Option<usize>), (usize, Option<usize>)) {
+        fn remaining(
+            live: bool,
+            hint: (usize, Option<usize>),
+            front: bool,
+            back: bool,
+        ) -> (usize, Option<usize>) {
+            let (lower, upper) = if live { hint } else { (0, Some(0)) };
+            let count = front as usize + back as usize;
+            (
+                lower.saturating_add(count),
+                upper.and_then(|upper| upper.checked_add(count)),
+            )
+        }
+
+        (
+            remaining(
+                self.state.left_live(),
+                self.left.size_hint(),
+                self.left_front.is_some(),
+                self.left_back.is_some(),
+            ),
+            remaining(
+                self.state.right_live(),
+                self.right.size_hint(),
+                self.right_front.is_some(),
+                self.right_back.is_some(),
+            ),
+        )
+    }
+
+    // Once a side is exhausted, its last item may still be held back by
+    // `next_back`.
+    #[inline]
+    fn pull_left(&mut self) -> Option<C::Entry> {
+        if self.state.left_live() {
+            match self.left.next() {
+                Some(item) => return Some(self.compare.entry(item)),
+                None => self.state = self.state.end_left(),
+            }
+        }
+        self.left_back.take()
+    }
+
+    #[inline]
+    fn pull_right(&mut self) -> Option<C::Entry> {
+        if self.state.right_live() {
+            match self.right.next() {
+                Some(item) => return Some(self.compare.entry(item)),
+                None => self.state = self.state.end_right(),
+            }
+        }
+        self.right_back.take()
+    }
+}
+
+impl<Left, Right, C> MergeIter<Left, Right, C>
+where
+    Left: DoubleEndedIterator,
+    Right: DoubleEndedIterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    #[inline]
+    fn pull_back_left(&mut self) -> Option<C::Entry> {
+        if self.state.left_live() {
+            match self.left.next_back() {
+                Some(item) => return Some(self.compare.entry(item)),
+                None => self.state = self.state.end_left(),
+            }
+        }
+        self.left_front.take()
+    }
+
+    #[inline]
+    fn pull_back_right(&mut self) -> Option<C::Entry> {
+        if self.state.right_live() {
+            match self.right.next_back() {
+                Some(item) => return Some(self.compare.entry(item)),
+                None => self.state = self.state.end_right(),
+            }
+        }
+        self.right_front.take()
+    }
+}
+
+impl<Left, Right, C> Iterator for MergeIter<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    type Item = Merged<Left::Item>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.left_front.is_none() {
+            self.left_front = self.pull_left();
+        }
+        if self.right_front.is_none() {
+            self.right_front = self.pull_right();
+        }
+
+        let order = match (&self.left_front, &self.right_front) {
+            (Some(left), Some(right)) => self.compare.compare(left, right),
+            (Some(_), None) => Less,
+            (None, Some(_)) => Greater,
+            (None, None) => return None,
+        };
+
+        match order {
+            Less => self
+                .left_front
+                .take()
+                .map(|left| Merged::Left(C::into_item(left))),
+            Greater => self
+                .right_front
+                .take()
+                .map(|right| Merged::Right(C::into_item(right))),
+            Equal => match (self.left_front.take(), self.right_front.take()) {
+                (Some(left), Some(right)) => {
+                    Some(Merged::Both(C::into_item(left), C::into_item(right)))
+                }
+                _ => None,
+            },
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let ((left_lo, left_hi), (right_lo, right_hi)) = self.side_hints();
+        let upper = match (left_hi, right_hi) {
+            (Some(left), Some(right)) => left.checked_add(right),
+            _ => None,
+        };
+
+        (left_lo.max(right_lo), upper)
+    }
+
+    fn fold<B, G>(self, init: B, mut g: G) -> B
+    where
+        G: FnMut(B, Self::Item) -> B,
+    {
+        let MergeIter {
+            left,
+            right,
+            mut compare,
+            state,
+            left_front,
+            right_front,
+            left_back,
+            right_back,
+        } = self;
+
+        let mut left = Remaining {
+            iter: if state.left_live() { Some(left) } else { None },
+            last: left_back.map(C::into_item),
+        };
+        let mut right = Remaining {
+            iter: if state.right_live() {
+                Some(right)
+            } else {
+                None
+            },
+            last: right_back.map(C::into_item),
+        };
+
+        let curr_left = left_front.or_else(|| left.next().map(|item| compare.entry(item)));
+        let curr_right = right_front.or_else(|| right.next().map(|item| compare.entry(item)));
+
+        let flow = try_merge_fold(
+            left,
+            right,
+            curr_left,
+            curr_right,
+            compare,
+            init,
+            |acc, item| ControlFlow::<Infallible, B>::Continue(g(acc, item)),
+        );
+        match flow {
+            ControlFlow::Continue(acc) => acc,
+            ControlFlow::Break(never) => match never {},
+        }
+    }
+}
+
+impl<Left, Right, C> DoubleEndedIterator for MergeIter<Left, Right, C>
+where
+    Left: DoubleEndedIterator,
+    Right: DoubleEndedIterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.left_back.is_none() {
+            self.left_back = self.pull_back_left();
+        }
+        if self.right_back.is_none() {
+            self.right_back = self.pull_back_right();
+        }
+
+        let order = match (&self.left_back, &self.right_back) {
+            (Some(left), Some(right)) => self.compare.compare(left, right),
+            (Some(_), None) => Greater,
+            (None, Some(_)) => Less,
+            (None, None) => return None,
+        };
+
+        match order {
+            Greater => self
+                .left_back
+                .take()
+                .map(|left| Merged::Left(C::into_item(left))),
+            Less => self
+                .right_back
+                .take()
+                .map(|right| Merged::Right(C::into_item(right))),
+            Equal => match (self.left_back.take(), self.right_back.take()) {
+                (Some(left), Some(right)) => {
+                    Some(Merged::Both(C::into_item(left), C::into_item(right)))
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+impl<Left, Right, C> FusedIterator for MergeIter<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+}
+
+/// What `fold` has left of one side: the input, unless it already ran dry,
+/// followed by the item held back by `next_back`.
+struct Remaining<I: Iterator> {
+    iter: Option<I>,
+    last: Option<I::Item>,
+}
+
+impl<I: Iterator> Iterator for Remaining<I> {
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if let Some(ref mut iter) = self.iter {
+            match iter.next() {
+                Some(item) => return Some(item),
+                None => self.iter = None,
+            }
+        }
+        self.last.take()
+    }
+}
+
+pub(crate) fn merge_with<Lhs, Rhs, C, F>(lhs: Lhs, rhs: Rhs, compare: C, mut f: F)
+where
+    Lhs: IntoIterator,
+    Rhs: IntoIterator<Item = Lhs::Item>,
+    C: Comparator<Lhs::Item>,
+    F: FnMut(Merged<Lhs::Item>),
+{
+    let _ = try_merge_with(lhs, rhs, compare, |item| {
+        f(item);
+        ControlFlow::<()>::Continue(())
+    });
+}
+
+/// `merge_with` for callbacks that can cut the merge short.
+pub(crate) fn try_merge_with<Lhs, Rhs, C, B, F>(
+    lhs: Lhs,
+    rhs: Rhs,
+    mut compare: C,
+    mut f: F,
+) -> ControlFlow<B>
+where
+    Lhs: IntoIterator,
+    Rhs: IntoIterator<Item = Lhs::Item>,
+    C: Comparator<Lhs::Item>,
+    F: FnMut(Merged<Lhs::Item>) -> ControlFlow<B>,
+{
+    let mut left = lhs.into_iter();
+    let mut right = rhs.into_iter();
+
+    let curr_left = left.next().map(|item| compare.entry(item));
+    let curr_right = right.next().map(|item| compare.entry(item));
+
+    try_merge_fold(
+        left,
+        right,
+        curr_left,
+        curr_right,
+        compare,
+        (),
+        |(), item| f(item),
+    )
+}
+
+/// The merge loop shared by the callback functions and `MergeIter::fold`.
+///
+/// `curr_left` and `curr_right` are the entries already taken from each side;
+/// a side whose entry is `None` is treated as exhausted and never polled.
+fn try_merge_fold<Left, Right, C, Acc, B, F>(
+    mut left: Left,
+    mut right: Right,
+    mut curr_left: Option<C::Entry>,
+    mut curr_right: Option<C::Entry>,
+    mut compare: C,
+    mut acc: Acc,
+    mut f: F,
+) -> ControlFlow<B, Acc>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+    F: FnMut(Acc, Merged<Left::Item>) -> ControlFlow<B, Acc>,
+{
+    loop {
+        match (curr_left.take(), curr_right.take()) {
+            (None, None) => return ControlFlow::Continue(acc),
+
+            (Some(entry), None) => {
+                acc = f(acc, Merged::Left(C::into_item(entry)))?;
+                for item in left {
+                    acc = f(acc, Merged::Left(item))?;
+                }
+                return ControlFlow::Continue(acc);
+            }
+
+            (None, Some(entry)) => {
+                acc = f(acc, Merged::Right(C::into_item(entry)))?;
+                for item in right {
+                    acc = f(acc, Merged::Right(item))?;
+                }
+                return ControlFlow::Continue(acc);
+            }
+
+            (Some(a), Some(b)) => match compare.compare(&a, &b) {
+                Greater => {
+                    acc = f(acc, Merged::Right(C::into_item(b)))?;
+                    curr_left = Some(a);
+                    curr_right = right.next().map(|item| compare.entry(item));
+                }
+
+                Less => {
+                    acc = f(acc, Merged::Left(C::into_item(a)))?;
+                    curr_left = left.next().map(|item| compare.entry(item));
+                    curr_right = Some(b);
+                }
+
+                Equal => {
+                    acc = f(acc, Merged::Both(C::into_item(a), C::into_item(b)))?;
+                    curr_left = left.next().map(|item| compare.entry(item));
+                    curr_right = right.next().map(|item| compare.entry(item));
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixtures::{stutter, tripwire, LEFT, RIGHT};
+    use std::vec;
+    use std::vec::Vec;
+    use SymmetricDifference;
+
+    #[test]
+    fn outer_merge_keeps_matched_pairs() {
+        let left = vec![(1, 'a'), (2, 'b'), (4, 'd')];
+        let right = vec![(2, 'B'), (3, 'C'), (4, 'D')];
+
+        let merged: Vec<_> = left
+            .into_iter()
+            .outer_merge_by_key(right, |&(id, _)| id)
+            .collect();
+
+        assert_eq!(
+            merged,
+            vec![
+                Merged::Left((1, 'a')),
+                Merged::Both((2, 'b'), (2, 'B')),
+                Merged::Right((3, 'C')),
+                Merged::Both((4, 'd'), (4, 'D')),
+            ]
+        );
+    }
+
+    #[test]
+    fn iter_outer_merge_matches_outer_merge() {
+        let (left, right) = (LEFT, RIGHT);
+
+        let mut internal = Vec::new();
+        left.iter_outer_merge(right, |x| internal.push(x));
+        let external: Vec<_> = left.outer_merge(right).collect();
+
+        assert_eq!(internal, external);
+        assert_eq!(external.iter().filter(|x| x.is_both()).count(), 5);
+    }
+
+    #[test]
+    fn merge_runs_from_both_ends() {
+        let (left, right) = (LEFT, RIGHT);
+
+        let forward: Vec<_> = left.outer_merge(right).collect();
+        let mut backward: Vec<_> = left.outer_merge(right).rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        // Both ends meet in the middle without losing or repeating the items
+        // held back by either end.
+        for split in 0..forward.len() {
+            let mut iter = left.outer_merge(right);
+            let mut front: Vec<_> = iter.by_ref().take(split).collect();
+            let mut back: Vec<_> = iter.by_ref().rev().collect();
+            back.reverse();
+            front.extend(back);
+            assert_eq!(front, forward);
+        }
+
+        let mut iter = left.outer_merge(right);
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        while let Some(item) = iter.next() {
+            front.push(item);
+            if let Some(item) = iter.next_back() {
+                back.push(item);
+            }
+        }
+        back.reverse();
+        front.extend(back);
+        assert_eq!(front, forward);
+    }
+
+    #[test]
+    fn exhausted_sides_are_never_polled_again() {
+        let (left, right) = (LEFT, RIGHT);
+        let expected: Vec<_> = left.outer_merge(right).collect();
+
+        let mut iter = tripwire(left).outer_merge(tripwire(right));
+        let merged: Vec<_> = iter.by_ref().collect();
+        assert_eq!(merged, expected);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(tripwire(left).difference(tripwire(right)).count(), 6);
+        assert_eq!(tripwire(left).difference(tripwire(&[])).count(), 9);
+        assert_eq!(tripwire(&[]).difference(tripwire(&[])).count(), 0);
+
+        for split in 0..expected.len() {
+            let mut iter = tripwire(left).outer_merge(tripwire(right));
+            let mut front: Vec<_> = iter.by_ref().take(split).collect();
+            let back: Vec<_> = iter.by_ref().rev().collect();
+            front.extend(back.into_iter().rev());
+            assert_eq!(front, expected);
+
+            let mut iter = tripwire(left).outer_merge(tripwire(right));
+            iter.by_ref().rev().take(split).for_each(drop);
+            assert_eq!(iter.count(), expected.len() - split);
+        }
+    }
+
+    #[test]
+    fn non_fused_inputs_stay_ended() {
+        let mut iter = stutter().outer_merge(vec![2, 9]);
+        let merged: Vec<_> = iter.by_ref().collect();
+        assert_eq!(
+            merged,
+            vec![
+                Merged::Left(1),
+                Merged::Both(2, 2),
+                Merged::Left(3),
+                Merged::Right(9),
+            ]
+        );
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+
+        let folded = stutter()
+            .difference(vec![2, 9])
+            .fold(0, |sum, tag| sum + tag.unwrap());
+        assert_eq!(folded, 13);
+    }
+}
@@ -0,0 +1,94 @@
+use std::panic;
+use std::thread;
+use std::vec::Vec;
+
+use {SymmetricDifference, Tag};
+
+/// Symmetric difference of two sorted slices computed on up to `threads`
+/// scoped worker threads.
+///
+/// Both slices are cut at the same pivot values, taken at even intervals from
+/// the larger slice, so that every copy of a value lands in the same
+/// partition. The partitions are diffed independently and concatenated in
+/// order, which gives exactly the output of `difference`.
+pub fn parallel_difference<'a, T>(left: &'a [T], right: &'a [T], threads: usize) -> Vec<Tag<&'a T>>
+where
+    T: Ord + Sync,
+{
+    let threads = threads.max(1);
+    if threads == 1 {
+        return left.difference(right).collect();
+    }
+
+    let larger = if left.len() >= right.len() {
+        left
+    } else {
+        right
+    };
+    let mut bounds = Vec::with_capacity(threads + 1);
+    bounds.push((0, 0));
+    for k in 1..threads {
+        if let Some(pivot) = larger.get(larger.len() * k / threads) {
+            bounds.push((
+                left.partition_point(|item| item < pivot),
+                right.partition_point(|item| item < pivot),
+            ));
+        }
+    }
+    bounds.push((left.len(), right.len()));
+
+    let parts: Vec<Vec<_>> = thread::scope(|scope| {
+        let workers: Vec<_> = bounds
+            .windows(2)
+            .map(|window| {
+                let (left, right) = (
+                    &left[window[0].0..window[1].0],
+                    &right[window[0].1..window[1].1],
+                );
+                scope.spawn(move || left.difference(right).collect::<Vec<_>>())
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    });
+
+    let mut result = Vec::with_capacity(parts.iter().map(Vec::len).sum());
+    for part in parts {
+        result.extend(part);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+
+    #[test]
+    fn matches_sequential_difference() {
+        let left: Vec<_> = (0..10_000).filter(|x| x % 13 != 0).collect();
+        let right: Vec<_> = (1..7_000).filter(|x| x % 23 != 0).collect();
+        let expected: Vec<_> = left.iter().difference(&right).collect();
+        let inverse: Vec<_> = right.iter().difference(&left).collect();
+
+        for threads in 0..9 {
+            assert_eq!(parallel_difference(&left, &right, threads), expected);
+            assert_eq!(parallel_difference(&right, &left, threads), inverse);
+        }
+    }
+
+    #[test]
+    fn keeps_duplicates_in_one_partition() {
+        let left = vec![1, 1, 1, 1, 2, 2, 3, 3, 3, 3, 3, 4];
+        let right = vec![1, 2, 2, 2, 3, 5];
+
+        assert_eq!(
+            parallel_difference(&left, &right, 4),
+            left.iter().difference(&right).collect::<Vec<_>>()
+        );
+        assert!(parallel_difference::<i32>(&[], &[], 4).is_empty());
+    }
+}
@@ -0,0 +1,464 @@
+#[cfg(feature = "alloc")]
+use alloc::collections::btree_map::{self, BTreeMap};
+#[cfg(feature = "alloc")]
+use alloc::collections::btree_set::{self, BTreeSet};
+use core::borrow::Borrow;
+use core::cmp::Ordering::*;
+#[cfg(feature = "alloc")]
+use core::ops::Bound::{self, Excluded, Included, Unbounded};
+use core::slice;
+
+use gallop::gallop;
+use {Side, Tag};
+
+/// A sorted iterator which can skip ahead to a key without visiting the items
+/// in between.
+///
+/// Items are compared with keys through `Borrow<Self::Key>`.
+pub trait SeekableSorted: Iterator {
+    type Key: Ord;
+
+    /// Skips every item less than `key`. Seeking to a key at or before the
+    /// current position does nothing.
+    fn seek(&mut self, key: &Self::Key);
+}
+
+impl<'a, T: Ord> SeekableSorted for slice::Iter<'a, T> {
+    type Key = T;
+
+    fn seek(&mut self, key: &T) {
+        let rest = self.as_slice();
+        *self = rest[gallop(rest, key)..].iter();
+    }
+}
+
+/// A peekable iterator which seeks the iterator it wraps. `core`'s
+/// `Peekable` gives no access to it, so it could only step.
+pub struct SeekPeekable<I: Iterator> {
+    iter: I,
+    /// The item `peek` took from `iter`, which is `None` if `iter` has ended.
+    peeked: Option<Option<I::Item>>,
+}
+
+pub fn seek_peekable<I: IntoIterator>(iter: I) -> SeekPeekable<I::IntoIter> {
+    SeekPeekable {
+        iter: iter.into_iter(),
+        peeked: None,
+    }
+}
+
+impl<I: Iterator> SeekPeekable<I> {
+    pub fn peek(&mut self) -> Option<&I::Item> {
+        let iter = &mut self.iter;
+        self.peeked.get_or_insert_with(|| iter.next()).as_ref()
+    }
+}
+
+impl<I: Iterator> Iterator for SeekPeekable<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        match self.peeked.take() {
+            Some(item) => item,
+            None => self.iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        match self.peeked {
+            Some(None) => (0, Some(0)),
+            Some(Some(_)) => (
+                lower.saturating_add(1),
+                upper.and_then(|upper| upper.checked_add(1)),
+            ),
+            None => (lower, upper),
+        }
+    }
+}
+
+impl<I> SeekableSorted for SeekPeekable<I>
+where
+    I: SeekableSorted,
+    I::Item: Borrow<I::Key>,
+{
+    type Key = I::Key;
+
+    fn seek(&mut self, key: &I::Key) {
+        match self.peeked.take() {
+            // Nothing before the peeked item is left to skip.
+            Some(Some(item)) if item.borrow() >= key => self.peeked = Some(Some(item)),
+            Some(None) => self.peeked = Some(None),
+            _ => self.iter.seek(key),
+        }
+    }
+}
+
+/// Whether anything before `key` is still to come, given that everything
+/// before `from` is gone.
+#[cfg(feature = "alloc")]
+fn is_ahead<T: Ord>(from: Bound<&T>, key: &T) -> bool {
+    match from {
+        Unbounded => true,
+        Included(from) | Excluded(from) => key > from,
+    }
+}
+
+/// Items of a `BTreeSet` in order, which seeks with a range query.
+#[cfg(feature = "alloc")]
+pub struct SetCursor<'a, T: 'a> {
+    set: &'a BTreeSet<T>,
+    range: btree_set::Range<'a, T>,
+    from: Bound<&'a T>,
+}
+
+#[cfg(feature = "alloc")]
+pub fn set_cursor<T: Ord>(set: &BTreeSet<T>) -> SetCursor<'_, T> {
+    SetCursor {
+        set,
+        range: set.range::<T, _>(..),
+        from: Unbounded,
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> Iterator for SetCursor<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let item = self.range.next()?;
+        self.from = Excluded(item);
+        Some(item)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: Ord> SeekableSorted for SetCursor<'a, T> {
+    type Key = T;
+
+    fn seek(&mut self, key: &T) {
+        if is_ahead(self.from, key) {
+            self.range = self.set.range((Included(key), Unbounded));
+            self.from = match self.range.clone().next() {
+                Some(first) => Included(first),
+                None => self.set.last().map_or(Unbounded, Excluded),
+            };
+        }
+    }
+}
+
+/// Keys of a `BTreeMap` in order, which seeks with a range query.
+#[cfg(feature = "alloc")]
+pub struct KeyCursor<'a, K: 'a, V: 'a> {
+    map: &'a BTreeMap<K, V>,
+    range: btree_map::Range<'a, K, V>,
+    from: Bound<&'a K>,
+}
+
+#[cfg(feature = "alloc")]
+pub fn key_cursor<K: Ord, V>(map: &BTreeMap<K, V>) -> KeyCursor<'_, K, V> {
+    KeyCursor {
+        map,
+        range: map.range::<K, _>(..),
+        from: Unbounded,
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, K, V> Iterator for KeyCursor<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        let (key, _) = self.range.next()?;
+        self.from = Excluded(key);
+        Some(key)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, K: Ord, V> SeekableSorted for KeyCursor<'a, K, V> {
+    type Key = K;
+
+    fn seek(&mut self, key: &K) {
+        if is_ahead(self.from, key) {
+            self.range = self.map.range((Included(key), Unbounded));
+            self.from = match self.range.clone().next() {
+                Some((first, _)) => Included(first),
+                None => self.map.keys().next_back().map_or(Unbounded, Excluded),
+            };
+        }
+    }
+}
+
+/// Symmetric difference of two seekable inputs.
+///
+/// Reporting both sides visits every item outside of the common ones, so the
+/// merge is linear apart from the initial seek of `range`. With `only`, the
+/// unreported side is sought to each item of the other instead of stepped
+/// through, and is never drained, so a small input can be checked against a
+/// large one in time that depends on the small one.
+pub struct SeekDiff<Left, Right>
+where
+    Left: SeekableSorted,
+{
+    left: Left,
+    right: Right,
+    left_front: Option<Left::Item>,
+    right_front: Option<Left::Item>,
+    left_live: bool,
+    right_live: bool,
+    start: Option<Left::Key>,
+    end: Option<Left::Key>,
+    only: Option<Side>,
+}
+
+pub fn seek_difference<Lhs, Rhs, T, K>(
+    left: Lhs,
+    right: Rhs,
+) -> SeekDiff<Lhs::IntoIter, Rhs::IntoIter>
+where
+    Lhs: IntoIterator<Item = T>,
+    Rhs: IntoIterator<Item = T>,
+    Lhs::IntoIter: SeekableSorted<Key = K>,
+    Rhs::IntoIter: SeekableSorted<Key = K>,
+    T: Borrow<K>,
+    K: Ord,
+{
+    SeekDiff {
+        left: left.into_iter(),
+        right: right.into_iter(),
+        left_front: None,
+        right_front: None,
+        left_live: true,
+        right_live: true,
+        start: None,
+        end: None,
+        only: None,
+    }
+}
+
+impl<Left, Right, T, K> SeekDiff<Left, Right>
+where
+    Left: SeekableSorted<Item = T, Key = K>,
+    Right: SeekableSorted<Item = T, Key = K>,
+    T: Borrow<K>,
+    K: Ord,
+{
+    /// Restricts the difference to items in `[lo, hi)`.
+    pub fn range(mut self, lo: K, hi: K) -> Self {
+        self.start = Some(lo);
+        self.end = Some(hi);
+        self
+    }
+
+    /// Reports only the items of one side.
+    pub fn only(mut self, side: Side) -> Self {
+        self.only = Some(side);
+        self
+    }
+}
+
+/// Takes the next item of `iter`, ending it for good at the first item not
+/// below `end`.
+fn pull<I, K>(iter: &mut I, live: &mut bool, end: &Option<K>) -> Option<I::Item>
+where
+    I: Iterator,
+    I::Item: Borrow<K>,
+    K: Ord,
+{
+    if !*live {
+        return None;
+    }
+
+    match iter.next() {
+        Some(item) if end.as_ref().is_none_or(|end| item.borrow() < end) => Some(item),
+        _ => {
+            *live = false;
+            None
+        }
+    }
+}
+
+impl<Left, Right, T, K> Iterator for SeekDiff<Left, Right>
+where
+    Left: SeekableSorted<Item = T, Key = K>,
+    Right: SeekableSorted<Item = T, Key = K>,
+    T: Borrow<K>,
+    K: Ord,
+{
+    type Item = Tag<T>;
+
+    fn next(&mut self) -> Option<Tag<T>> {
+        if let Some(start) = self.start.take() {
+            self.left.seek(&start);
+            self.right.seek(&start);
+        }
+
+        loop {
+            if self.left_front.is_none() {
+                self.left_front = pull(&mut self.left, &mut self.left_live, &self.end);
+            }
+            if self.right_front.is_none() {
+                self.right_front = pull(&mut self.right, &mut self.right_live, &self.end);
+            }
+
+            match (self.left_front.take(), self.right_front.take()) {
+                (None, None) => return None,
+
+                (Some(a), None) => {
+                    if self.only == Some(Side::Right) {
+                        self.left_live = false;
+                        self.right_live = false;
+                        return None;
+                    }
+                    return Some(Tag::Left(a));
+                }
+
+                (None, Some(b)) => {
+                    if self.only == Some(Side::Left) {
+                        self.left_live = false;
+                        self.right_live = false;
+                        return None;
+                    }
+                    return Some(Tag::Right(b));
+                }
+
+                (Some(a), Some(b)) => match a.borrow().cmp(b.borrow()) {
+                    Less if self.only == Some(Side::Right) => {
+                        self.left.seek(b.borrow());
+                        self.right_front = Some(b);
+                    }
+
+                    Greater if self.only == Some(Side::Left) => {
+                        self.right.seek(a.borrow());
+                        self.left_front = Some(a);
+                    }
+
+                    Less => {
+                        self.right_front = Some(b);
+                        return Some(Tag::Left(a));
+                    }
+
+                    Greater => {
+                        self.left_front = Some(a);
+                        return Some(Tag::Right(b));
+                    }
+
+                    Equal => (),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+    use SymmetricDifference;
+
+    #[test]
+    fn seeking_skips_smaller_items() {
+        let items: Vec<_> = (0..100).map(|x| x * 2).collect();
+
+        let mut iter = items.iter();
+        iter.seek(&31);
+        assert_eq!(iter.next(), Some(&32));
+        iter.seek(&10);
+        assert_eq!(iter.next(), Some(&34));
+
+        let mut iter = seek_peekable(items.iter());
+        assert_eq!(iter.peek(), Some(&&0));
+        iter.seek(&50);
+        assert_eq!(iter.peek(), Some(&&50));
+        iter.seek(&41);
+        assert_eq!(iter.next(), Some(&50));
+        iter.seek(&197);
+        assert_eq!(iter.size_hint(), (1, Some(1)));
+        assert_eq!(iter.next(), Some(&198));
+        assert_eq!(iter.peek(), None);
+        iter.seek(&0);
+        assert_eq!(iter.next(), None);
+
+        #[cfg(feature = "alloc")]
+        {
+            let set: BTreeSet<_> = items.iter().cloned().collect();
+            let mut iter = set_cursor(&set);
+            iter.seek(&31);
+            assert_eq!(iter.next(), Some(&32));
+            iter.seek(&10);
+            assert_eq!(iter.next(), Some(&34));
+            iter.seek(&500);
+            assert_eq!(iter.next(), None);
+            iter.seek(&0);
+            assert_eq!(iter.next(), None);
+
+            let map: BTreeMap<_, _> = items.iter().map(|&x| (x, ())).collect();
+            let mut iter = key_cursor(&map);
+            iter.seek(&77);
+            assert_eq!(iter.next(), Some(&78));
+        }
+    }
+
+    #[test]
+    fn one_sided_difference_matches_full_merge() {
+        let small = vec![3, 50, 51, 51, 999, 1500];
+        let large: Vec<_> = (0..1000).filter(|x| x % 7 != 0).collect();
+
+        let full: Vec<_> = small.iter().difference(&large).collect();
+        let left: Vec<_> = full.iter().cloned().filter(|tag| tag.is_left()).collect();
+        let right: Vec<_> = full.iter().cloned().filter(|tag| tag.is_right()).collect();
+
+        assert_eq!(seek_difference(&small, &large).collect::<Vec<_>>(), full);
+        assert_eq!(
+            seek_difference(&small, &large)
+                .only(Side::Left)
+                .collect::<Vec<_>>(),
+            left
+        );
+        assert_eq!(
+            seek_difference(&small, &large)
+                .only(Side::Right)
+                .collect::<Vec<_>>(),
+            right
+        );
+        assert_eq!(
+            seek_difference(&large, &small)
+                .only(Side::Right)
+                .collect::<Vec<_>>(),
+            left.iter()
+                .map(|tag| Tag::Right(*tag.value()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn range_limits_both_inputs() {
+        let left: Vec<_> = (0..300).filter(|x| x % 3 == 0).collect();
+        let right: Vec<_> = (0..300).filter(|x| x % 5 == 0).collect();
+
+        let expected: Vec<_> = left
+            .iter()
+            .difference(&right)
+            .filter(|tag| (100..200).contains(*tag.value()))
+            .collect();
+
+        assert_eq!(
+            seek_difference(&left, &right)
+                .range(100, 200)
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(
+            seek_difference(&left, &right)
+                .range(100, 200)
+                .only(Side::Left)
+                .collect::<Vec<_>>(),
+            expected
+                .iter()
+                .cloned()
+                .filter(|tag| tag.is_left())
+                .collect::<Vec<_>>()
+        );
+    }
+}
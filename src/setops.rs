@@ -0,0 +1,384 @@
+use core::cmp::Ordering::*;
+
+use compare::{Comparator, Natural};
+use merge::{self, MergeIter, Merged, State};
+
+/// Items found in either input. Where both inputs hold an item, the left one
+/// is kept.
+pub struct Union<Left, Right, C = Natural>
+where
+    Left: Iterator,
+    Right: Iterator,
+    C: Comparator<Left::Item>,
+{
+    inner: MergeIter<Left, Right, C>,
+}
+
+impl<Left, Right, C> Union<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    pub(crate) fn new(left: Left, right: Right, compare: C) -> Self {
+        Union {
+            inner: MergeIter::new(left, right, compare),
+        }
+    }
+}
+
+impl<Left, Right, C> Iterator for Union<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    type Item = Left::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.inner.next()? {
+            Merged::Left(item) | Merged::Right(item) | Merged::Both(item, _) => Some(item),
+        }
+    }
+}
+
+/// Items found in both inputs, taken from the left.
+///
+/// Ends for good as soon as either input returns `None`.
+pub struct Intersection<Left, Right, C = Natural> {
+    left: Left,
+    right: Right,
+    compare: C,
+    state: State,
+}
+
+impl<Left, Right, C> Intersection<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    pub(crate) fn new(left: Left, right: Right, compare: C) -> Self {
+        Intersection {
+            left,
+            right,
+            compare,
+            state: State::BothLive,
+        }
+    }
+
+    fn next_common(&mut self) -> Option<Left::Item> {
+        let mut left = self.compare.entry(self.left.next()?);
+        let mut right = self.compare.entry(self.right.next()?);
+
+        loop {
+            match self.compare.compare(&left, &right) {
+                Less => left = self.compare.entry(self.left.next()?),
+                Greater => right = self.compare.entry(self.right.next()?),
+                Equal => return Some(C::into_item(left)),
+            }
+        }
+    }
+}
+
+impl<Left, Right, C> Iterator for Intersection<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    type Item = Left::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.state != State::BothLive {
+            return None;
+        }
+
+        let item = self.next_common();
+        if item.is_none() {
+            self.state = State::Done;
+        }
+        item
+    }
+}
+
+/// Items found in the left input but not in the right.
+///
+/// Each input is polled until it first returns `None` and never again.
+pub struct Difference<Left, Right, C = Natural>
+where
+    Left: Iterator,
+    Right: Iterator,
+    C: Comparator<Left::Item>,
+{
+    left: Left,
+    right: Right,
+    compare: C,
+    state: State,
+    rem: Option<C::Entry>,
+}
+
+impl<Left, Right, C> Difference<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    pub(crate) fn new(left: Left, right: Right, compare: C) -> Self {
+        Difference {
+            left,
+            right,
+            compare,
+            state: State::BothLive,
+            rem: None,
+        }
+    }
+
+    #[inline]
+    fn pull_left(&mut self) -> Option<C::Entry> {
+        if self.state.left_live() {
+            match self.left.next() {
+                Some(item) => return Some(self.compare.entry(item)),
+                None => self.state = self.state.end_left(),
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn pull_right(&mut self) -> Option<C::Entry> {
+        if let Some(right) = self.rem.take() {
+            return Some(right);
+        }
+        if self.state.right_live() {
+            match self.right.next() {
+                Some(item) => return Some(self.compare.entry(item)),
+                None => self.state = self.state.end_right(),
+            }
+        }
+        None
+    }
+}
+
+impl<Left, Right, C> Iterator for Difference<Left, Right, C>
+where
+    Left: Iterator,
+    Right: Iterator<Item = Left::Item>,
+    C: Comparator<Left::Item>,
+{
+    type Item = Left::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut left = self.pull_left()?;
+
+        loop {
+            let right = match self.pull_right() {
+                Some(right) => right,
+                None => return Some(C::into_item(left)),
+            };
+
+            match self.compare.compare(&left, &right) {
+                Less => {
+                    self.rem = Some(right);
+                    return Some(C::into_item(left));
+                }
+
+                Equal => left = self.pull_left()?,
+                Greater => (),
+            }
+        }
+    }
+}
+
+pub(crate) fn union_with<Lhs, Rhs, C, F>(lhs: Lhs, rhs: Rhs, compare: C, mut f: F)
+where
+    Lhs: IntoIterator,
+    Rhs: IntoIterator<Item = Lhs::Item>,
+    C: Comparator<Lhs::Item>,
+    F: FnMut(Lhs::Item),
+{
+    merge::merge_with(lhs, rhs, compare, |item| match item {
+        Merged::Left(item) | Merged::Right(item) | Merged::Both(item, _) => f(item),
+    })
+}
+
+pub(crate) fn intersection_with<Lhs, Rhs, C, F>(lhs: Lhs, rhs: Rhs, mut compare: C, mut f: F)
+where
+    Lhs: IntoIterator,
+    Rhs: IntoIterator<Item = Lhs::Item>,
+    C: Comparator<Lhs::Item>,
+    F: FnMut(Lhs::Item),
+{
+    let mut left = lhs.into_iter();
+    let mut right = rhs.into_iter();
+
+    let (mut a, mut b) = match (left.next(), right.next()) {
+        (Some(a), Some(b)) => (compare.entry(a), compare.entry(b)),
+        _ => return,
+    };
+
+    loop {
+        match compare.compare(&a, &b) {
+            Less => match left.next() {
+                Some(item) => a = compare.entry(item),
+                None => return,
+            },
+
+            Greater => match right.next() {
+                Some(item) => b = compare.entry(item),
+                None => return,
+            },
+
+            Equal => {
+                f(C::into_item(a));
+                match (left.next(), right.next()) {
+                    (Some(x), Some(y)) => {
+                        a = compare.entry(x);
+                        b = compare.entry(y);
+                    }
+                    _ => return,
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn difference_with<Lhs, Rhs, C, F>(lhs: Lhs, rhs: Rhs, mut compare: C, mut f: F)
+where
+    Lhs: IntoIterator,
+    Rhs: IntoIterator<Item = Lhs::Item>,
+    C: Comparator<Lhs::Item>,
+    F: FnMut(Lhs::Item),
+{
+    let mut left = lhs.into_iter();
+    let mut right = rhs.into_iter();
+
+    let mut a = match left.next() {
+        Some(item) => compare.entry(item),
+        None => return,
+    };
+
+    let mut b = match right.next() {
+        Some(item) => compare.entry(item),
+        None => {
+            f(C::into_item(a));
+            return left.for_each(f);
+        }
+    };
+
+    loop {
+        match compare.compare(&a, &b) {
+            Less => {
+                f(C::into_item(a));
+                match left.next() {
+                    Some(item) => a = compare.entry(item),
+                    None => return,
+                }
+            }
+
+            Equal => match (left.next(), right.next()) {
+                (Some(x), Some(y)) => {
+                    a = compare.entry(x);
+                    b = compare.entry(y);
+                }
+                (Some(x), None) => {
+                    f(x);
+                    return left.for_each(f);
+                }
+                (None, _) => return,
+            },
+
+            Greater => match right.next() {
+                Some(item) => b = compare.entry(item),
+                None => {
+                    f(C::into_item(a));
+                    return left.for_each(f);
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fixtures::{stutter, LEFT, RIGHT};
+    use std::collections::BTreeSet;
+    use std::prelude::v1::*;
+    use SymmetricDifference;
+
+    fn sets() -> (BTreeSet<i32>, BTreeSet<i32>) {
+        (
+            LEFT.iter().cloned().collect(),
+            RIGHT.iter().cloned().collect(),
+        )
+    }
+
+    #[test]
+    fn set_operations_match_btreeset() {
+        let (left, right) = sets();
+
+        let union: Vec<_> = LEFT.sorted_union(RIGHT).cloned().collect();
+        let intersection: Vec<_> = LEFT.sorted_intersection(RIGHT).cloned().collect();
+        let difference: Vec<_> = LEFT.sorted_difference(RIGHT).cloned().collect();
+        let reverse: Vec<_> = RIGHT.sorted_difference(LEFT).cloned().collect();
+
+        assert_eq!(union, left.union(&right).cloned().collect::<Vec<_>>());
+        assert_eq!(
+            intersection,
+            left.intersection(&right).cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            difference,
+            BTreeSet::difference(&left, &right)
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            reverse,
+            BTreeSet::difference(&right, &left)
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn internal_set_operations_match_external() {
+        let mut union = Vec::new();
+        let mut intersection = Vec::new();
+        let mut difference = Vec::new();
+        let mut reverse = Vec::new();
+
+        LEFT.iter_sorted_union(RIGHT, |&x| union.push(x));
+        LEFT.iter_sorted_intersection(RIGHT, |&x| intersection.push(x));
+        LEFT.iter_sorted_difference(RIGHT, |&x| difference.push(x));
+        RIGHT.iter_sorted_difference(LEFT, |&x| reverse.push(x));
+
+        assert_eq!(union, LEFT.sorted_union(RIGHT).cloned().collect::<Vec<_>>());
+        assert_eq!(
+            intersection,
+            LEFT.sorted_intersection(RIGHT).cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            difference,
+            LEFT.sorted_difference(RIGHT).cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            reverse,
+            RIGHT.sorted_difference(LEFT).cloned().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn non_fused_inputs_stay_ended() {
+        let mut iter = stutter().sorted_intersection(vec![2, 6]);
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(iter.next(), None);
+
+        let mut iter = stutter().sorted_difference(vec![2, 6]);
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(iter.next(), None);
+
+        let iter = (1..10).sorted_difference(stutter());
+        assert_eq!(iter.collect::<Vec<_>>(), vec![4, 5, 6, 7, 8, 9]);
+    }
+}
@@ -0,0 +1,536 @@
+use alloc::vec::Vec;
+
+use {SymmetricDifference, Tag};
+
+/// Primitive integers with a vectorized symmetric difference.
+pub trait Primitive: Copy + Ord + private::Sealed {
+    #[doc(hidden)]
+    fn difference_into(
+        left: &[Self],
+        right: &[Self],
+        left_only: &mut Vec<Self>,
+        right_only: &mut Vec<Self>,
+    );
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for u32 {}
+    impl Sealed for u64 {}
+}
+
+/// Appends the items found only in `left` to `left_only` and those found only
+/// in `right` to `right_only`, comparing whole blocks of each input at once
+/// where the CPU allows.
+///
+/// The block comparison needs strictly increasing inputs. From the first
+/// repeated item on, both inputs take the generic path instead, which pairs
+/// repeated items off one by one as `difference` does.
+pub fn primitive_difference_into<T: Primitive>(
+    left: &[T],
+    right: &[T],
+    left_only: &mut Vec<T>,
+    right_only: &mut Vec<T>,
+) {
+    T::difference_into(left, right, left_only, right_only)
+}
+
+impl Primitive for u32 {
+    #[cfg(target_arch = "x86_64")]
+    fn difference_into(
+        left: &[u32],
+        right: &[u32],
+        left_only: &mut Vec<u32>,
+        right_only: &mut Vec<u32>,
+    ) {
+        if has_avx2() {
+            // SAFETY: AVX2 support was just detected.
+            unsafe { x86::difference_avx2_u32(left, right, left_only, right_only) }
+        } else {
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            unsafe { x86::difference_sse2_u32(left, right, left_only, right_only) }
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fn difference_into(
+        left: &[u32],
+        right: &[u32],
+        left_only: &mut Vec<u32>,
+        right_only: &mut Vec<u32>,
+    ) {
+        scalar(left, right, left_only, right_only)
+    }
+}
+
+impl Primitive for u64 {
+    #[cfg(target_arch = "x86_64")]
+    fn difference_into(
+        left: &[u64],
+        right: &[u64],
+        left_only: &mut Vec<u64>,
+        right_only: &mut Vec<u64>,
+    ) {
+        if has_avx2() {
+            // SAFETY: AVX2 support was just detected.
+            unsafe { x86::difference_avx2_u64(left, right, left_only, right_only) }
+        } else {
+            scalar(left, right, left_only, right_only)
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fn difference_into(
+        left: &[u64],
+        right: &[u64],
+        left_only: &mut Vec<u64>,
+        right_only: &mut Vec<u64>,
+    ) {
+        scalar(left, right, left_only, right_only)
+    }
+}
+
+// Without `std` there is no runtime detection, so AVX2 is only used when the
+// build targets it.
+#[cfg(target_arch = "x86_64")]
+fn has_avx2() -> bool {
+    #[cfg(feature = "std")]
+    {
+        std::is_x86_feature_detected!("avx2")
+    }
+
+    #[cfg(not(feature = "std"))]
+    {
+        cfg!(target_feature = "avx2")
+    }
+}
+
+fn scalar<T: Copy + Ord>(left: &[T], right: &[T], left_only: &mut Vec<T>, right_only: &mut Vec<T>) {
+    left.iter().iter_difference(right, |tag| match tag {
+        Tag::Left(&x) => left_only.push(x),
+        Tag::Right(&x) => right_only.push(x),
+    });
+}
+
+/// Compares a block of `WIDTH` items from each side, returning for each side
+/// a bitmask of the lanes whose value occurs anywhere in the other block.
+trait Kernel<T> {
+    const WIDTH: usize;
+
+    /// # Safety
+    ///
+    /// Both pointers must be valid for reads of `WIDTH` items, and the CPU
+    /// must support the instructions the kernel is built on.
+    unsafe fn matches(left: *const T, right: *const T) -> (u32, u32);
+
+    /// Whether any two adjacent items of the block are equal.
+    ///
+    /// # Safety
+    ///
+    /// As for `matches`.
+    unsafe fn repeats(block: *const T) -> bool;
+}
+
+// Whether the block starting at `start` repeats an item, either within the
+// block or against the item before it.
+//
+// # Safety
+//
+// As for `Kernel::repeats`, with the block inside `items`.
+#[inline(always)]
+unsafe fn block_repeats<T, K>(items: &[T], start: usize) -> bool
+where
+    T: Copy + Ord,
+    K: Kernel<T>,
+{
+    (start > 0 && items[start - 1] == items[start]) || K::repeats(items.as_ptr().add(start))
+}
+
+#[inline(always)]
+fn push_unmatched<T: Copy>(block: &[T], mask: u32, out: &mut Vec<T>) {
+    for (lane, &item) in block.iter().enumerate() {
+        if mask & (1 << lane) == 0 {
+            out.push(item);
+        }
+    }
+}
+
+// Advances through both inputs a block at a time, always retiring the block
+// with the smaller maximum. For strictly increasing inputs this compares
+// every pair of blocks whose ranges overlap, so a lane left unmatched when
+// its block is retired has no equal in the other input. Each block is
+// checked for repeats before it is compared, and the first one found hands
+// the rest of both inputs to the scalar merge.
+//
+// # Safety
+//
+// The CPU must support the instructions `K` is built on.
+#[inline(always)]
+unsafe fn block_merge<T, K>(
+    left: &[T],
+    right: &[T],
+    left_only: &mut Vec<T>,
+    right_only: &mut Vec<T>,
+) where
+    T: Copy + Ord,
+    K: Kernel<T>,
+{
+    let width = K::WIDTH;
+    let (mut i, mut j) = (0, 0);
+    let (mut left_mask, mut right_mask) = (0, 0);
+
+    while i + width <= left.len() && j + width <= right.len() {
+        // SAFETY: the loop condition keeps both blocks inside their slices,
+        // and the caller vouches for the CPU.
+        if block_repeats::<T, K>(left, i) || block_repeats::<T, K>(right, j) {
+            break;
+        }
+        let (a, b) = K::matches(left.as_ptr().add(i), right.as_ptr().add(j));
+        left_mask |= a;
+        right_mask |= b;
+
+        let left_max = left[i + width - 1];
+        let right_max = right[j + width - 1];
+
+        if left_max <= right_max {
+            push_unmatched(&left[i..i + width], left_mask, left_only);
+            i += width;
+            left_mask = 0;
+        }
+
+        if right_max <= left_max {
+            push_unmatched(&right[j..j + width], right_mask, right_only);
+            j += width;
+            right_mask = 0;
+        }
+    }
+
+    // Lanes already matched in the current block were matched against blocks
+    // that have been retired, so they must not reach the scalar merge. A block
+    // with repeats was never compared, so its mask is empty.
+    let left_rest = left[i..]
+        .iter()
+        .enumerate()
+        .filter(|&(lane, _)| lane >= width || left_mask & (1 << lane) == 0)
+        .map(|(_, &item)| item);
+    let right_rest = right[j..]
+        .iter()
+        .enumerate()
+        .filter(|&(lane, _)| lane >= width || right_mask & (1 << lane) == 0)
+        .map(|(_, &item)| item);
+
+    left_rest.iter_difference(right_rest, |tag| match tag {
+        Tag::Left(x) => left_only.push(x),
+        Tag::Right(x) => right_only.push(x),
+    });
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+
+    use super::{block_merge, Kernel, Vec};
+
+    struct Sse2U32;
+
+    impl Kernel<u32> for Sse2U32 {
+        const WIDTH: usize = 4;
+
+        #[inline(always)]
+        unsafe fn matches(left: *const u32, right: *const u32) -> (u32, u32) {
+            matches_sse2_u32(left, right)
+        }
+
+        #[inline(always)]
+        unsafe fn repeats(block: *const u32) -> bool {
+            repeats_sse2_u32(block)
+        }
+    }
+
+    // Lanes of `a` equal to any lane of `b`, found by comparing `a` against
+    // every rotation of `b`.
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn any_eq_u32x4(a: __m128i, b: __m128i) -> __m128i {
+        let r1 = _mm_shuffle_epi32::<0b00_11_10_01>(b);
+        let r2 = _mm_shuffle_epi32::<0b01_00_11_10>(b);
+        let r3 = _mm_shuffle_epi32::<0b10_01_00_11>(b);
+
+        _mm_or_si128(
+            _mm_or_si128(_mm_cmpeq_epi32(a, b), _mm_cmpeq_epi32(a, r1)),
+            _mm_or_si128(_mm_cmpeq_epi32(a, r2), _mm_cmpeq_epi32(a, r3)),
+        )
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn matches_sse2_u32(left: *const u32, right: *const u32) -> (u32, u32) {
+        let a = _mm_loadu_si128(left as *const __m128i);
+        let b = _mm_loadu_si128(right as *const __m128i);
+
+        (
+            _mm_movemask_ps(_mm_castsi128_ps(any_eq_u32x4(a, b))) as u32,
+            _mm_movemask_ps(_mm_castsi128_ps(any_eq_u32x4(b, a))) as u32,
+        )
+    }
+
+    // Each lane is compared with the next one. The last lane wraps around to
+    // the first, so its bit is dropped.
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn repeats_sse2_u32(block: *const u32) -> bool {
+        let a = _mm_loadu_si128(block as *const __m128i);
+        let next = _mm_shuffle_epi32::<0b00_11_10_01>(a);
+        let eq = _mm_movemask_ps(_mm_castsi128_ps(_mm_cmpeq_epi32(a, next)));
+        eq & 0b0111 != 0
+    }
+
+    struct Avx2U32;
+
+    impl Kernel<u32> for Avx2U32 {
+        const WIDTH: usize = 8;
+
+        #[inline(always)]
+        unsafe fn matches(left: *const u32, right: *const u32) -> (u32, u32) {
+            matches_avx2_u32(left, right)
+        }
+
+        #[inline(always)]
+        unsafe fn repeats(block: *const u32) -> bool {
+            repeats_avx2_u32(block)
+        }
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn any_eq_u32x8(a: __m256i, b: __m256i) -> __m256i {
+        let mut acc = _mm256_cmpeq_epi32(a, b);
+        let mut rotation = _mm256_setr_epi32(1, 2, 3, 4, 5, 6, 7, 0);
+        let step = _mm256_set1_epi32(1);
+        let lanes = _mm256_set1_epi32(7);
+
+        for _ in 1..8 {
+            let rotated = _mm256_permutevar8x32_epi32(b, rotation);
+            acc = _mm256_or_si256(acc, _mm256_cmpeq_epi32(a, rotated));
+            rotation = _mm256_and_si256(_mm256_add_epi32(rotation, step), lanes);
+        }
+
+        acc
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn matches_avx2_u32(left: *const u32, right: *const u32) -> (u32, u32) {
+        let a = _mm256_loadu_si256(left as *const __m256i);
+        let b = _mm256_loadu_si256(right as *const __m256i);
+
+        (
+            _mm256_movemask_ps(_mm256_castsi256_ps(any_eq_u32x8(a, b))) as u32,
+            _mm256_movemask_ps(_mm256_castsi256_ps(any_eq_u32x8(b, a))) as u32,
+        )
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn repeats_avx2_u32(block: *const u32) -> bool {
+        let a = _mm256_loadu_si256(block as *const __m256i);
+        let next = _mm256_permutevar8x32_epi32(a, _mm256_setr_epi32(1, 2, 3, 4, 5, 6, 7, 0));
+        let eq = _mm256_movemask_ps(_mm256_castsi256_ps(_mm256_cmpeq_epi32(a, next)));
+        eq & 0x7f != 0
+    }
+
+    struct Avx2U64;
+
+    impl Kernel<u64> for Avx2U64 {
+        const WIDTH: usize = 4;
+
+        #[inline(always)]
+        unsafe fn matches(left: *const u64, right: *const u64) -> (u32, u32) {
+            matches_avx2_u64(left, right)
+        }
+
+        #[inline(always)]
+        unsafe fn repeats(block: *const u64) -> bool {
+            repeats_avx2_u64(block)
+        }
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn any_eq_u64x4(a: __m256i, b: __m256i) -> __m256i {
+        let r1 = _mm256_permute4x64_epi64::<0b00_11_10_01>(b);
+        let r2 = _mm256_permute4x64_epi64::<0b01_00_11_10>(b);
+        let r3 = _mm256_permute4x64_epi64::<0b10_01_00_11>(b);
+
+        _mm256_or_si256(
+            _mm256_or_si256(_mm256_cmpeq_epi64(a, b), _mm256_cmpeq_epi64(a, r1)),
+            _mm256_or_si256(_mm256_cmpeq_epi64(a, r2), _mm256_cmpeq_epi64(a, r3)),
+        )
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn matches_avx2_u64(left: *const u64, right: *const u64) -> (u32, u32) {
+        let a = _mm256_loadu_si256(left as *const __m256i);
+        let b = _mm256_loadu_si256(right as *const __m256i);
+
+        (
+            _mm256_movemask_pd(_mm256_castsi256_pd(any_eq_u64x4(a, b))) as u32,
+            _mm256_movemask_pd(_mm256_castsi256_pd(any_eq_u64x4(b, a))) as u32,
+        )
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn repeats_avx2_u64(block: *const u64) -> bool {
+        let a = _mm256_loadu_si256(block as *const __m256i);
+        let next = _mm256_permute4x64_epi64::<0b00_11_10_01>(a);
+        let eq = _mm256_movemask_pd(_mm256_castsi256_pd(_mm256_cmpeq_epi64(a, next)));
+        eq & 0b0111 != 0
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn difference_sse2_u32(
+        left: &[u32],
+        right: &[u32],
+        left_only: &mut Vec<u32>,
+        right_only: &mut Vec<u32>,
+    ) {
+        block_merge::<u32, Sse2U32>(left, right, left_only, right_only)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn difference_avx2_u32(
+        left: &[u32],
+        right: &[u32],
+        left_only: &mut Vec<u32>,
+        right_only: &mut Vec<u32>,
+    ) {
+        block_merge::<u32, Avx2U32>(left, right, left_only, right_only)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn difference_avx2_u64(
+        left: &[u64],
+        right: &[u64],
+        left_only: &mut Vec<u64>,
+        right_only: &mut Vec<u64>,
+    ) {
+        block_merge::<u64, Avx2U64>(left, right, left_only, right_only)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+
+    // Strictly increasing values with gaps drawn from a small LCG, so that
+    // runs, matches and block boundaries fall in varied places.
+    fn sorted_set(seed: u64, len: usize, spread: u64) -> Vec<u64> {
+        let mut state = seed;
+        let mut value = 0;
+        (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                value += 1 + (state >> 33) % spread;
+                value
+            })
+            .collect()
+    }
+
+    fn generic<T: Copy + Ord>(left: &[T], right: &[T]) -> (Vec<T>, Vec<T>) {
+        let (mut left_only, mut right_only) = (Vec::new(), Vec::new());
+        scalar(left, right, &mut left_only, &mut right_only);
+        (left_only, right_only)
+    }
+
+    fn cases() -> Vec<(Vec<u64>, Vec<u64>)> {
+        let mut cases = vec![(vec![], vec![]), (vec![1, 2, 3], vec![]), (vec![], vec![5])];
+        for seed in 0..20 {
+            let len = (seed as usize * 37) % 300;
+            cases.push((
+                sorted_set(seed, len, 4),
+                sorted_set(seed + 100, len / 2 + 3, 6),
+            ));
+        }
+        cases
+    }
+
+    #[test]
+    fn u64_matches_generic_path() {
+        for (left, right) in cases() {
+            let (mut left_only, mut right_only) = (Vec::new(), Vec::new());
+            primitive_difference_into(&left, &right, &mut left_only, &mut right_only);
+
+            assert_eq!((left_only, right_only), generic(&left, &right));
+        }
+    }
+
+    #[test]
+    fn u32_matches_generic_path() {
+        for (left, right) in cases() {
+            let left: Vec<u32> = left.into_iter().map(|x| x as u32).collect();
+            let right: Vec<u32> = right.into_iter().map(|x| x as u32).collect();
+
+            let (mut left_only, mut right_only) = (Vec::new(), Vec::new());
+            primitive_difference_into(&left, &right, &mut left_only, &mut right_only);
+            assert_eq!((left_only, right_only), generic(&left, &right));
+
+            #[cfg(target_arch = "x86_64")]
+            {
+                let (mut left_only, mut right_only) = (Vec::new(), Vec::new());
+                // SAFETY: SSE2 is part of the x86_64 baseline.
+                unsafe { x86::difference_sse2_u32(&left, &right, &mut left_only, &mut right_only) };
+                assert_eq!((left_only, right_only), generic(&left, &right));
+            }
+        }
+    }
+
+    #[test]
+    fn repeated_items_are_paired_off() {
+        let left: Vec<u64> = vec![1; 8].into_iter().chain(2..=9).collect();
+        let right: Vec<u64> = (1..=8).chain(10..=17).collect();
+
+        let (mut left_only, mut right_only) = (Vec::new(), Vec::new());
+        primitive_difference_into(&left, &right, &mut left_only, &mut right_only);
+        assert_eq!(left_only, vec![1, 1, 1, 1, 1, 1, 1, 9]);
+        assert_eq!((left_only, right_only), generic(&left, &right));
+
+        let left: Vec<u32> = left.into_iter().map(|x| x as u32).collect();
+        let right: Vec<u32> = right.into_iter().map(|x| x as u32).collect();
+
+        let (mut left_only, mut right_only) = (Vec::new(), Vec::new());
+        primitive_difference_into(&right, &left, &mut right_only, &mut left_only);
+        assert_eq!((right_only, left_only), generic(&right, &left));
+
+        // Halving the values repeats items at varied points, after some blocks
+        // have already been compared.
+        for (left, right) in cases() {
+            let left: Vec<u64> = left.into_iter().map(|x| x / 2).collect();
+            let right: Vec<u64> = right.into_iter().map(|x| x / 2).collect();
+
+            let (mut left_only, mut right_only) = (Vec::new(), Vec::new());
+            primitive_difference_into(&left, &right, &mut left_only, &mut right_only);
+            assert_eq!((left_only, right_only), generic(&left, &right));
+
+            let left: Vec<u32> = left.into_iter().map(|x| x as u32).collect();
+            let right: Vec<u32> = right.into_iter().map(|x| x as u32).collect();
+
+            let (mut left_only, mut right_only) = (Vec::new(), Vec::new());
+            primitive_difference_into(&left, &right, &mut left_only, &mut right_only);
+            assert_eq!((left_only, right_only), generic(&left, &right));
+
+            #[cfg(target_arch = "x86_64")]
+            {
+                let (mut left_only, mut right_only) = (Vec::new(), Vec::new());
+                // SAFETY: SSE2 is part of the x86_64 baseline.
+                unsafe { x86::difference_sse2_u32(&left, &right, &mut left_only, &mut right_only) };
+                assert_eq!((left_only, right_only), generic(&left, &right));
+            }
+        }
+    }
+}
@@ -0,0 +1,354 @@
+#[cfg(feature = "alloc")]
+use alloc::collections::btree_set;
+#[cfg(feature = "alloc")]
+use alloc::vec::{self, Vec};
+#[cfg(feature = "alloc")]
+use core::iter::FromIterator;
+use core::iter::{FusedIterator, Peekable};
+use core::ops::Deref;
+use core::slice;
+
+use compare::{ByValue, Comparator, Natural, Valued};
+#[cfg(feature = "alloc")]
+use seek::{KeyCursor, SetCursor};
+use seek::{SeekPeekable, SeekableSorted};
+use {SymDiffIter, Tag};
+
+/// Marks iterators whose items come in ascending order. Tagged items are
+/// ordered by value, as `ByValue` compares them.
+///
+/// Only the entry points in this module insist on it. Implementing it for an
+/// iterator that is out of order gives wrong differences, just as passing
+/// one to `difference` does, but nothing worse.
+pub trait SortedIterator: Iterator {}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> SortedIterator for btree_set::Iter<'a, T> {}
+#[cfg(feature = "alloc")]
+impl<T> SortedIterator for btree_set::IntoIter<T> {}
+#[cfg(feature = "alloc")]
+impl<'a, T> SortedIterator for btree_set::Range<'a, T> {}
+#[cfg(feature = "alloc")]
+impl<'a, T: Ord> SortedIterator for btree_set::Union<'a, T> {}
+#[cfg(feature = "alloc")]
+impl<'a, T: Ord> SortedIterator for btree_set::Intersection<'a, T> {}
+#[cfg(feature = "alloc")]
+impl<'a, T: Ord> SortedIterator for btree_set::Difference<'a, T> {}
+#[cfg(feature = "alloc")]
+impl<'a, T: Ord> SortedIterator for btree_set::SymmetricDifference<'a, T> {}
+#[cfg(feature = "alloc")]
+impl<'a, T> SortedIterator for SetCursor<'a, T> {}
+#[cfg(feature = "alloc")]
+impl<'a, K, V> SortedIterator for KeyCursor<'a, K, V> {}
+
+impl<I: SortedIterator> SortedIterator for Peekable<I> {}
+impl<I: SortedIterator> SortedIterator for SeekPeekable<I> {}
+
+/// Tagged items come sorted by value. Only the natural order and `ByValue`
+/// qualify, since the marker does not record any other.
+impl<Left, Right> SortedIterator for SymDiffIter<Left, Right, Natural>
+where
+    Left: SortedIterator,
+    Left::Item: Ord,
+    Right: SortedIterator<Item = Left::Item>,
+{
+}
+
+impl<Left, Right> SortedIterator for SymDiffIter<Left, Right, ByValue>
+where
+    Left: SortedIterator,
+    Left::Item: Valued,
+    Right: SortedIterator<Item = Left::Item>,
+{
+}
+
+/// A slice known to be sorted.
+#[repr(transparent)]
+pub struct SortedSlice<T>([T]);
+
+impl<T: Ord> SortedSlice<T> {
+    /// Returns `None` if `slice` is not sorted.
+    pub fn new(slice: &[T]) -> Option<&Self> {
+        if slice.is_sorted() {
+            Some(SortedSlice::new_unchecked(slice))
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> SortedIter<'_, T> {
+        SortedIter(self.0.iter())
+    }
+}
+
+impl<T> SortedSlice<T> {
+    fn new_unchecked(slice: &[T]) -> &Self {
+        // SAFETY: `SortedSlice<T>` is a transparent wrapper around `[T]`.
+        unsafe { &*(slice as *const [T] as *const SortedSlice<T>) }
+    }
+}
+
+impl<T> Deref for SortedSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a SortedSlice<T> {
+    type Item = &'a T;
+    type IntoIter = SortedIter<'a, T>;
+
+    fn into_iter(self) -> SortedIter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Items of a `SortedSlice` or `SortedVec` by reference.
+#[derive(Clone)]
+pub struct SortedIter<'a, T: 'a>(slice::Iter<'a, T>);
+
+impl<'a, T> Iterator for SortedIter<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for SortedIter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.0.next_back()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for SortedIter<'a, T> {}
+impl<'a, T> FusedIterator for SortedIter<'a, T> {}
+impl<'a, T> SortedIterator for SortedIter<'a, T> {}
+
+impl<'a, T: Ord> SeekableSorted for SortedIter<'a, T> {
+    type Key = T;
+
+    fn seek(&mut self, key: &T) {
+        self.0.seek(key);
+    }
+}
+
+/// A `Vec` known to be sorted.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SortedVec<T>(Vec<T>);
+
+#[cfg(feature = "alloc")]
+impl<T: Ord> SortedVec<T> {
+    /// Gives `items` back if they are not sorted.
+    pub fn new(items: Vec<T>) -> Result<Self, Vec<T>> {
+        if items.is_sorted() {
+            Ok(SortedVec(items))
+        } else {
+            Err(items)
+        }
+    }
+
+    pub fn from_unsorted(mut items: Vec<T>) -> Self {
+        items.sort();
+        SortedVec(items)
+    }
+
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Deref for SortedVec<T> {
+    type Target = SortedSlice<T>;
+
+    fn deref(&self) -> &SortedSlice<T> {
+        SortedSlice::new_unchecked(&self.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Ord> FromIterator<T> for SortedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        SortedVec::from_unsorted(iter.into_iter().collect())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: Ord> IntoIterator for &'a SortedVec<T> {
+    type Item = &'a T;
+    type IntoIter = SortedIter<'a, T>;
+
+    fn into_iter(self) -> SortedIter<'a, T> {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> IntoIterator for SortedVec<T> {
+    type Item = T;
+    type IntoIter = SortedIntoIter<T>;
+
+    fn into_iter(self) -> SortedIntoIter<T> {
+        SortedIntoIter(self.0.into_iter())
+    }
+}
+
+/// Items of a `SortedVec` by value.
+#[cfg(feature = "alloc")]
+pub struct SortedIntoIter<T>(vec::IntoIter<T>);
+
+#[cfg(feature = "alloc")]
+impl<T> Iterator for SortedIntoIter<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> DoubleEndedIterator for SortedIntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.next_back()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> ExactSizeIterator for SortedIntoIter<T> {}
+#[cfg(feature = "alloc")]
+impl<T> FusedIterator for SortedIntoIter<T> {}
+#[cfg(feature = "alloc")]
+impl<T> SortedIterator for SortedIntoIter<T> {}
+
+/// Symmetric difference of two inputs marked as sorted. The inputs may be
+/// earlier differences, whose tagged items are compared by value, so chained
+/// differences stay marked.
+pub fn symmetric_difference<Lhs, Rhs>(
+    left: Lhs,
+    right: Rhs,
+) -> SymDiffIter<Lhs::IntoIter, Rhs::IntoIter, ByValue>
+where
+    Lhs: IntoIterator,
+    Lhs::IntoIter: SortedIterator,
+    Lhs::Item: Valued,
+    Rhs: IntoIterator<Item = Lhs::Item>,
+    Rhs::IntoIter: SortedIterator,
+{
+    SymDiffIter::new(left.into_iter(), right.into_iter(), ByValue)
+}
+
+/// Symmetric difference of two marked inputs under `compare`, which must be
+/// the order they are sorted in.
+pub fn symmetric_difference_by<Lhs, Rhs, C>(
+    left: Lhs,
+    right: Rhs,
+    compare: C,
+) -> SymDiffIter<Lhs::IntoIter, Rhs::IntoIter, C>
+where
+    Lhs: IntoIterator,
+    Lhs::IntoIter: SortedIterator,
+    Rhs: IntoIterator<Item = Lhs::Item>,
+    Rhs::IntoIter: SortedIterator,
+    C: Comparator<Lhs::Item>,
+{
+    SymDiffIter::new(left.into_iter(), right.into_iter(), compare)
+}
+
+pub fn iter_symmetric_difference<Lhs, Rhs, F>(left: Lhs, right: Rhs, f: F)
+where
+    Lhs: IntoIterator,
+    Lhs::IntoIter: SortedIterator,
+    Lhs::Item: Valued,
+    Rhs: IntoIterator<Item = Lhs::Item>,
+    Rhs::IntoIter: SortedIterator,
+    F: FnMut(Tag<Lhs::Item>),
+{
+    ::iter_difference_with(left, right, ByValue, f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+
+    #[test]
+    fn slices_are_verified() {
+        assert!(SortedSlice::new(&[2, 1]).is_none());
+
+        let left = SortedSlice::new(&[1, 2, 2, 5]).unwrap();
+        let right = SortedSlice::new(&[2, 3]).unwrap();
+        let values: Vec<_> = symmetric_difference(left, right).collect();
+        assert_eq!(
+            values,
+            vec![Tag::Left(&1), Tag::Left(&2), Tag::Right(&3), Tag::Left(&5)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn vecs_are_sorted_or_verified() {
+        use std::collections::BTreeSet;
+
+        assert_eq!(SortedVec::new(vec![3, 1]), Err(vec![3, 1]));
+        let left: SortedVec<_> = vec![5, 1, 3, 4].into_iter().collect();
+        assert_eq!(SortedVec::new(vec![1, 3, 4, 5]).as_ref(), Ok(&left));
+
+        let right: BTreeSet<_> = [2, 3, 4, 6].iter().cloned().collect();
+        let mut seen = Vec::new();
+        iter_symmetric_difference(&left, &right, |tag| seen.push(*tag.unwrap()));
+        assert_eq!(seen, vec![1, 2, 5, 6]);
+
+        seen.clear();
+        iter_symmetric_difference(left, right, |tag| seen.push(tag.unwrap()));
+        assert_eq!(seen, vec![1, 2, 5, 6]);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn chained_differences_compose() {
+        let a = SortedVec::from_unsorted(vec![1, 2, 3]);
+        let b = SortedVec::from_unsorted(vec![2, 3, 4]);
+        let c = SortedVec::from_unsorted(vec![3, 4, 5]);
+        let nothing = SortedVec::default();
+
+        // (a ^ b) ^ (c ^ nothing) holds the items found in an odd number of
+        // inputs.
+        let values: Vec<_> = symmetric_difference(
+            symmetric_difference(&a, &b),
+            symmetric_difference(&c, &nothing),
+        )
+        .map(|tag| **tag.unwrap().value())
+        .collect();
+        assert_eq!(values, vec![1, 3, 5]);
+
+        // Each level of chaining is still marked, so it can feed the next:
+        // (a ^ b ^ c) ^ (b ^ c) leaves a.
+        let mut seen = Vec::new();
+        iter_symmetric_difference(
+            symmetric_difference(
+                symmetric_difference(&a, &b),
+                symmetric_difference(&c, &nothing),
+            ),
+            symmetric_difference(
+                symmetric_difference(&b, &c),
+                symmetric_difference(&nothing, &nothing),
+            ),
+            |tag| seen.push(**tag.unwrap().unwrap().value()),
+        );
+        assert_eq!(seen, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter;
+use std::mem;
+use std::vec::{self, Vec};
+
+use Tag;
+
+/// Output order of `unsorted_difference`.
+///
+/// Either way, left-only items come first, in the order the left input
+/// yields them, and the right-only items follow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Order {
+    /// Right-only items in hash map order, which changes from run to run.
+    Unspecified,
+    /// Right-only items in the order the right input yielded them.
+    Input,
+}
+
+/// Symmetric difference of two unsorted inputs.
+///
+/// The right input is indexed up front; the left input is streamed against
+/// the index. Repeated items are paired off one by one as in `difference`.
+pub struct UnsortedDiff<Left: Iterator> {
+    left: Left,
+    right: Index<Left::Item>,
+    rest: Option<vec::IntoIter<Tag<Left::Item>>>,
+}
+
+enum Index<T> {
+    /// Each distinct item, with any further copies of it.
+    Grouped(HashMap<T, Vec<T>>),
+    /// Each distinct item with its position, and any further copies with
+    /// theirs, as well as the number of items read.
+    Ordered {
+        groups: HashMap<T, Group<T>>,
+        len: usize,
+    },
+}
+
+/// Copies of one item, in input order after the one used as the key.
+struct Group<T> {
+    position: usize,
+    copies: Vec<(usize, T)>,
+    /// How many copies, counted from the first, have been paired off.
+    matched: usize,
+}
+
+impl<T: Hash + Eq> Index<T> {
+    fn new<I: Iterator<Item = T>>(items: I, order: Order) -> Self {
+        match order {
+            Order::Unspecified => {
+                let mut groups: HashMap<T, Vec<T>> = HashMap::new();
+                for item in items {
+                    if let Some(copies) = groups.get_mut(&item) {
+                        copies.push(item);
+                        continue;
+                    }
+                    groups.insert(item, Vec::new());
+                }
+                Index::Grouped(groups)
+            }
+
+            Order::Input => {
+                let mut groups: HashMap<T, Group<T>> = HashMap::new();
+                let mut len = 0;
+                for (position, item) in items.enumerate() {
+                    len += 1;
+                    if let Some(group) = groups.get_mut(&item) {
+                        group.copies.push((position, item));
+                        continue;
+                    }
+                    let group = Group {
+                        position,
+                        copies: Vec::new(),
+                        matched: 0,
+                    };
+                    groups.insert(item, group);
+                }
+                Index::Ordered { groups, len }
+            }
+        }
+    }
+
+    /// Removes one unmatched item equal to `item`, if there is one.
+    fn remove(&mut self, item: &T) -> bool {
+        match self {
+            Index::Grouped(groups) => match groups.get_mut(item) {
+                Some(copies) => {
+                    if copies.pop().is_none() {
+                        groups.remove(item);
+                    }
+                    true
+                }
+                None => false,
+            },
+
+            Index::Ordered { groups, .. } => match groups.get_mut(item) {
+                Some(group) if group.matched <= group.copies.len() => {
+                    group.matched += 1;
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// Drains the unmatched items, tagged as right-only.
+    fn take_unmatched(&mut self) -> Vec<Tag<T>> {
+        match self {
+            Index::Grouped(groups) => mem::take(groups)
+                .into_iter()
+                .flat_map(|(item, copies)| Some(item).into_iter().chain(copies))
+                .map(Tag::Right)
+                .collect(),
+            Index::Ordered { groups, len } => {
+                let mut slots: Vec<Option<T>> = iter::repeat_with(|| None).take(*len).collect();
+                for (item, group) in mem::take(groups) {
+                    let copies = iter::once((group.position, item)).chain(group.copies);
+                    for (position, item) in copies.skip(group.matched) {
+                        slots[position] = Some(item);
+                    }
+                }
+                slots.into_iter().flatten().map(Tag::Right).collect()
+            }
+        }
+    }
+}
+
+impl<Left> UnsortedDiff<Left>
+where
+    Left: Iterator,
+    Left::Item: Hash + Eq,
+{
+    pub(crate) fn new<Right>(left: Left, right: Right, order: Order) -> Self
+    where
+        Right: Iterator<Item = Left::Item>,
+    {
+        UnsortedDiff {
+            left,
+            right: Index::new(right, order),
+            rest: None,
+        }
+    }
+
+    /// Both sides interleaved by value, as `difference` gives them for sorted
+    /// inputs. Nothing is yielded until both inputs have been read.
+    pub(crate) fn sorted<Right>(left: Left, right: Right) -> Self
+    where
+        Right: Iterator<Item = Left::Item>,
+        Left::Item: Ord,
+    {
+        let mut diff = UnsortedDiff::new(left, right, Order::Unspecified);
+        let mut tags: Vec<_> = diff.by_ref().collect();
+        tags.sort_by(|a, b| a.value().cmp(b.value()));
+        diff.rest = Some(tags.into_iter());
+        diff
+    }
+}
+
+impl<Left> Iterator for UnsortedDiff<Left>
+where
+    Left: Iterator,
+    Left::Item: Hash + Eq,
+{
+    type Item = Tag<Left::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(ref mut rest) = self.rest {
+                return rest.next();
+            }
+
+            match self.left.next() {
+                Some(item) => {
+                    if !self.right.remove(&item) {
+                        return Some(Tag::Left(item));
+                    }
+                }
+                None => self.rest = Some(self.right.take_unmatched().into_iter()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+    use SymmetricDifference;
+
+    #[test]
+    fn same_tags_as_sorted_difference() {
+        let left = vec![8, 1, 16, 5, 2, 2, 13, 4, 15, 6, 1];
+        let right = vec![3, 2, 7, 4, 8, 6, 5, 7];
+
+        let mut expected: Vec<_> = {
+            let (mut left, mut right) = (left.clone(), right.clone());
+            left.sort();
+            right.sort();
+            left.difference(right).collect()
+        };
+        expected.sort_by_key(|tag| (tag.is_left(), *tag.value()));
+
+        for &order in &[Order::Unspecified, Order::Input] {
+            let mut found: Vec<_> = left
+                .iter()
+                .cloned()
+                .unsorted_difference(right.iter().cloned(), order)
+                .collect();
+            found.sort_by_key(|tag| (tag.is_left(), *tag.value()));
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn input_order_is_kept() {
+        let left = vec!["d", "a", "x", "b", "a"];
+        let right = vec!["z", "a", "y", "d", "z"];
+
+        let diff: Vec<_> = left.unsorted_difference(right, Order::Input).collect();
+        assert_eq!(
+            diff,
+            vec![
+                Tag::Left("x"),
+                Tag::Left("b"),
+                Tag::Left("a"),
+                Tag::Right("z"),
+                Tag::Right("y"),
+                Tag::Right("z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn items_need_not_be_ordered() {
+        #[derive(Debug, PartialEq, Eq, Hash)]
+        struct Id(u8);
+
+        let diff: Vec<_> = vec![Id(1), Id(2)]
+            .unsorted_difference(vec![Id(2), Id(3)], Order::Input)
+            .collect();
+        assert_eq!(diff, vec![Tag::Left(Id(1)), Tag::Right(Id(3))]);
+    }
+
+    #[test]
+    fn sorted_order_matches_difference() {
+        let left = vec![8, 1, 16, 5, 2, 2, 13, 4, 15, 6, 1];
+        let right = vec![3, 2, 7, 4, 8, 6, 5, 7];
+
+        let expected: Vec<_> = {
+            let (mut left, mut right) = (left.clone(), right.clone());
+            left.sort();
+            right.sort();
+            left.difference(right).collect()
+        };
+        let found: Vec<_> = left.unsorted_difference_sorted(right).collect();
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,184 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::mem;
+#[cfg(feature = "std")]
+use std::fmt::Display;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+/// Receives the items of a merge, with a separate hook for each side.
+///
+/// Driven by `visit_difference`. Since each hook takes `&mut self`, state kept
+/// per side needs no shared cell.
+pub trait DiffVisitor<T> {
+    type Output;
+
+    fn on_left(&mut self, item: T);
+
+    fn on_right(&mut self, item: T);
+
+    /// Called with the pair of items found on both sides. Ignores them by
+    /// default.
+    fn on_both(&mut self, left: T, right: T) {
+        let _ = (left, right);
+    }
+
+    /// Called once the merge is over; its value is returned by
+    /// `visit_difference`.
+    fn finish(&mut self) -> Self::Output;
+}
+
+/// Counts the items of each kind.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub left: usize,
+    pub right: usize,
+    pub both: usize,
+}
+
+impl<T> DiffVisitor<T> for Counts {
+    type Output = Counts;
+
+    fn on_left(&mut self, _: T) {
+        self.left += 1;
+    }
+
+    fn on_right(&mut self, _: T) {
+        self.right += 1;
+    }
+
+    fn on_both(&mut self, _: T, _: T) {
+        self.both += 1;
+    }
+
+    fn finish(&mut self) -> Counts {
+        *self
+    }
+}
+
+/// Collects the items found on one side only, and hands both `Vec`s back from
+/// `finish`.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default)]
+pub struct Collect<T> {
+    pub left: Vec<T>,
+    pub right: Vec<T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T> DiffVisitor<T> for Collect<T> {
+    type Output = (Vec<T>, Vec<T>);
+
+    fn on_left(&mut self, item: T) {
+        self.left.push(item);
+    }
+
+    fn on_right(&mut self, item: T) {
+        self.right.push(item);
+    }
+
+    fn finish(&mut self) -> (Vec<T>, Vec<T>) {
+        (mem::take(&mut self.left), mem::take(&mut self.right))
+    }
+}
+
+/// Writes the items found on one side only to that side's sink, one per line.
+///
+/// After the first write error nothing more is written. `finish` flushes both
+/// sinks and returns that error, if any.
+#[cfg(feature = "std")]
+pub struct WriteLines<L, R> {
+    left: L,
+    right: R,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<L: Write, R: Write> WriteLines<L, R> {
+    pub fn new(left: L, right: R) -> Self {
+        WriteLines {
+            left,
+            right,
+            error: None,
+        }
+    }
+
+    pub fn into_inner(self) -> (L, R) {
+        (self.left, self.right)
+    }
+}
+
+/// Writes `item` unless an earlier write failed, keeping the first error.
+#[cfg(feature = "std")]
+fn write_line<W: Write, T: Display>(sink: &mut W, error: &mut Option<io::Error>, item: T) {
+    if error.is_none() {
+        if let Err(e) = writeln!(sink, "{}", item) {
+            *error = Some(e);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L: Write, R: Write, T: Display> DiffVisitor<T> for WriteLines<L, R> {
+    type Output = io::Result<()>;
+
+    fn on_left(&mut self, item: T) {
+        write_line(&mut self.left, &mut self.error, item);
+    }
+
+    fn on_right(&mut self, item: T) {
+        write_line(&mut self.right, &mut self.error, item);
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.left.flush()?;
+        self.right.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+    use SymmetricDifference;
+
+    #[test]
+    fn counts_cover_common_items() {
+        let counts = (&[1, 2, 3, 5]).visit_difference(&[2, 4, 5], &mut Counts::default());
+        assert_eq!(
+            counts,
+            Counts {
+                left: 2,
+                right: 1,
+                both: 2,
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn collect_splits_sides() {
+        let mut collect = Collect::default();
+        let (left, right) = vec![1, 2, 3, 5].visit_difference(vec![2, 4, 5, 6], &mut collect);
+        assert_eq!(left, vec![1, 3]);
+        assert_eq!(right, vec![4, 6]);
+        assert!(collect.left.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn lines_go_to_their_own_sink() {
+        let mut lines = WriteLines::new(Vec::new(), Vec::new());
+        (&["a", "b", "d"])
+            .visit_difference(&["b", "c"], &mut lines)
+            .unwrap();
+
+        let (left, right) = lines.into_inner();
+        assert_eq!(left, b"a\nd\n");
+        assert_eq!(right, b"c\n");
+    }
+}
@@ -0,0 +1,44 @@
+//! Uses the core API from a `no_std` crate, so that building the tests with
+//! `--no-default-features` checks that nothing here needs `alloc` or `std`.
+
+#![no_std]
+
+// The test harness itself still needs `std`.
+extern crate std;
+extern crate symdiff;
+
+use symdiff::{SymmetricDifference, Tag};
+
+#[test]
+fn difference_runs_without_allocating() {
+    let left = [1, 2, 4, 5, 6, 8, 13, 15, 16];
+    let right = [2, 3, 4, 5, 6, 7, 8];
+
+    let mut found = [(false, 0); 6];
+    let mut count = 0;
+    for tag in left.iter().difference(right.iter()) {
+        found[count] = (tag.is_left(), *tag.unwrap());
+        count += 1;
+    }
+
+    assert_eq!(count, 6);
+    assert_eq!(
+        found,
+        [
+            (true, 1),
+            (false, 3),
+            (false, 7),
+            (true, 13),
+            (true, 15),
+            (true, 16)
+        ]
+    );
+
+    let mut sum = 0;
+    left.iter().iter_difference(right.iter(), |tag| {
+        if let Tag::Right(&x) = tag {
+            sum += x;
+        }
+    });
+    assert_eq!(sum, 10);
+}